@@ -1,53 +1,438 @@
-use super::{Coordinates, Player, Side};
+use super::board::Board;
+use super::mcts::{self, Playable};
+use super::quantum::{QuantumBoard, QuantumMove};
+use super::rules::Rules;
+use super::ultimate::UltimateBoard;
+use super::{Coordinates, Move, Outcome, Player};
 
 use rand::seq::SliceRandom;
-use std::collections::{HashMap, HashSet};
+use rand::Rng;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, PartialEq)]
+/// How long search-based AIs think about a move when they are not given a
+/// deadline.
+pub const DEFAULT_THINK_TIME: Duration = Duration::from_secs(1);
+
+/// Score of a won position. Wins found deeper in the tree score a little less
+/// so that the AI goes for the quickest win and the slowest loss.
+const WIN_SCORE: i32 = 1_000_000;
+const INFINITY: i32 = WIN_SCORE + 1;
+
+/// How deep MiniMax searches at most in games which may go on forever.
+const MAX_SEARCH_DEPTH: usize = 32;
+
+/// What a search makes of a position, from the point of view of the player
+/// to move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Evaluation {
+    /// The player wins within the given number of moves, counting both
+    /// players' moves.
+    Win(i32),
+    /// The player loses within the given number of moves.
+    Loss(i32),
+    /// The heuristic score of an undecided position, higher the better.
+    Score(i32),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 #[non_exhaustive]
 pub enum AI {
+    #[default]
     Random,
     MiniMax,
+    MonteCarlo,
 }
 
 impl AI {
-    pub fn get_marker(
+    pub const ALL: [AI; 3] = [AI::Random, AI::MiniMax, AI::MonteCarlo];
+
+    /// Picks the position the AI wants to mark for the player, playing to win
+    /// under the given rules. Any randomness is drawn from `rng`, so seeding it
+    /// makes the AI's moves reproducible.
+    pub fn get_marker<R: Rng + ?Sized>(
         &self,
-        marked_positions: &HashMap<Coordinates, Player>,
-        side: &Side,
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        rng: &mut R,
     ) -> Coordinates {
+        let deadline = Instant::now() + DEFAULT_THINK_TIME;
+        self.get_marker_within(board, rules, player, rng, deadline)
+    }
+
+    /// Like `get_marker`, but search-based AIs stop thinking once the deadline
+    /// passes and play the best move found so far. Since how far the search
+    /// gets depends on the machine, only moves found within the deadline are
+    /// reproducible from a seed.
+    pub fn get_marker_within<R: Rng + ?Sized>(
+        &self,
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> Coordinates {
+        self.get_move_within(board, rules, player, rng, deadline)
+            .position
+    }
+
+    /// Like `get_marker_within`, but also tells which symbol to place, for
+    /// rules which let the player pick either.
+    pub fn get_move_within<R: Rng + ?Sized>(
+        &self,
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> Move {
         match self {
-            Self::Random => Self::random_mark(marked_positions, side),
-            // Self::MiniMax => Self::minimax_mark(marked_positions, side),
-            _ => panic!("AI algorithm {:?} is not implemented yet!", self),
+            Self::Random => *rules.legal_moves(board, player).choose(rng).unwrap(),
+            Self::MiniMax => Self::minimax_move(board, rules, player, rng, deadline),
+            Self::MonteCarlo => {
+                let position = ClassicPosition {
+                    board: board.clone(),
+                    rules,
+                    to_move: player,
+                    outcome: None,
+                };
+                mcts::search(&position, rng, deadline)
+            }
         }
     }
 
-    fn random_mark(marked_positions: &HashMap<Coordinates, Player>, side: &Side) -> Coordinates {
-        let Side(side) = side;
-        let all_moves = {
-            let mut all_moves = HashSet::with_capacity(side.pow(2).into());
-            for x in 0..(*side as i16) {
-                for y in 0..(*side as i16) {
-                    all_moves.insert(Coordinates { x, y });
+    /// Decides whether to swap sides under a swap rule, once the opening
+    /// stones are on the board and it is the player's turn. The AI swaps when
+    /// it judges the player to move to be worse off than their opponent.
+    pub fn wants_swap<R: Rng + ?Sized>(
+        &self,
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> bool {
+        match self {
+            Self::Random => rng.gen_bool(0.5),
+            Self::MiniMax => Self::iterative_deepening(board, rules, player, deadline).0 < 0,
+            Self::MonteCarlo => {
+                let position = ClassicPosition {
+                    board: board.clone(),
+                    rules,
+                    to_move: player,
+                    outcome: None,
+                };
+                mcts::search_with_value(&position, rng, deadline).1 < 0.5
+            }
+        }
+    }
+
+    /// Picks the position the AI wants to mark on an ultimate tic-tac-toe board
+    /// for the player to move.
+    pub fn get_ultimate_marker<R: Rng + ?Sized>(
+        &self,
+        board: &UltimateBoard,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> Coordinates {
+        match self {
+            Self::Random => *board.legal_moves().choose(rng).unwrap(),
+            Self::MonteCarlo => mcts::search(board, rng, deadline),
+            _ => panic!(
+                "AI algorithm {:?} does not support ultimate tic-tac-toe!",
+                self
+            ),
+        }
+    }
+
+    /// Whether the AI can play ultimate tic-tac-toe.
+    pub fn supports_ultimate(&self) -> bool {
+        matches!(self, Self::Random | Self::MonteCarlo)
+    }
+
+    /// Picks the move the AI wants to make on a quantum tic-tac-toe board for
+    /// the player to move, which may be collapsing a cycle.
+    pub fn get_quantum_move<R: Rng + ?Sized>(
+        &self,
+        board: &QuantumBoard,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> QuantumMove {
+        match self {
+            Self::Random => *board.legal_moves().choose(rng).unwrap(),
+            Self::MonteCarlo => mcts::search(board, rng, deadline),
+            _ => panic!(
+                "AI algorithm {:?} does not support quantum tic-tac-toe!",
+                self
+            ),
+        }
+    }
+
+    /// Whether the AI can play quantum tic-tac-toe.
+    pub fn supports_quantum(&self) -> bool {
+        matches!(self, Self::Random | Self::MonteCarlo)
+    }
+
+    /// Judges the position for the player to move the way MiniMax does, and
+    /// finds the best move there, if there is any. Only two-player games can
+    /// be evaluated.
+    pub fn evaluate(
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        deadline: Instant,
+    ) -> (Evaluation, Option<Move>) {
+        let (score, best_moves) = Self::iterative_deepening(board, rules, player, deadline);
+        let evaluation = if score >= WIN_SCORE / 2 {
+            Evaluation::Win(WIN_SCORE - score)
+        } else if score <= -WIN_SCORE / 2 {
+            Evaluation::Loss(WIN_SCORE + score)
+        } else {
+            Evaluation::Score(score)
+        };
+        (evaluation, best_moves.first().copied())
+    }
+
+    /// Searches the game tree with alpha-beta pruning, one ply deeper at a time,
+    /// until the outcome is certain or the deadline passes. Ties between equally
+    /// good moves are broken at random.
+    fn minimax_move<R: Rng + ?Sized>(
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        rng: &mut R,
+        deadline: Instant,
+    ) -> Move {
+        let (_, best_moves) = Self::iterative_deepening(board, rules, player, deadline);
+        *best_moves.choose(rng).unwrap()
+    }
+
+    /// The score of the position for the player to move and the moves which
+    /// achieve it, from the deepest search finished before the deadline. The
+    /// score is 0 if not even the shallowest search finished.
+    fn iterative_deepening(
+        board: &Board,
+        rules: &dyn Rules,
+        player: Player,
+        deadline: Instant,
+    ) -> (i32, Vec<Move>) {
+        let mut board = board.clone();
+        let mut best = (0, rules.legal_moves(&board, player));
+        let max_depth = rules.max_moves(&board).unwrap_or(MAX_SEARCH_DEPTH);
+        for depth in 1..=max_depth {
+            match Self::search_root(&mut board, rules, player, depth, deadline) {
+                Some((score, moves)) => {
+                    best = (score, moves);
+                    // The outcome is certain once a win or loss is found.
+                    if score.abs() >= WIN_SCORE - max_depth as i32 {
+                        break;
+                    }
                 }
+                None => break,
             }
-            all_moves
+        }
+        best
+    }
+
+    /// Scores every move at the root and returns the best score along with all
+    /// the moves that achieve it, or `None` if the deadline passed.
+    fn search_root(
+        board: &mut Board,
+        rules: &dyn Rules,
+        player: Player,
+        depth: usize,
+        deadline: Instant,
+    ) -> Option<(i32, Vec<Move>)> {
+        let mut best_score = -INFINITY;
+        let mut best_moves = Vec::new();
+        for next in rules.legal_moves(board, player) {
+            let undo = rules.apply(board, &next).unwrap();
+            let score = if let Some(outcome) = rules.outcome(board, &next.position, player) {
+                Some(Self::score_outcome(outcome, player, 1))
+            } else {
+                // Searching with a window just below the best score so far
+                // tells apart moves which tie with it from worse ones.
+                Self::search_after(
+                    board,
+                    rules,
+                    player,
+                    depth - 1,
+                    2,
+                    best_score - 1,
+                    INFINITY,
+                    deadline,
+                )
+            };
+            rules.undo(board, &undo);
+            let score = score?;
+            if score > best_score {
+                best_score = score;
+                best_moves.clear();
+            }
+            if score == best_score {
+                best_moves.push(next);
+            }
+        }
+        Some((best_score, best_moves))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        board: &mut Board,
+        rules: &dyn Rules,
+        player: Player,
+        depth: usize,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        deadline: Instant,
+    ) -> Option<i32> {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        if depth == 0 {
+            return Some(rules.evaluate(board, player).clamp(alpha, beta));
+        }
+        for next in rules.legal_moves(board, player) {
+            let undo = rules.apply(board, &next).unwrap();
+            let score = if let Some(outcome) = rules.outcome(board, &next.position, player) {
+                Some(Self::score_outcome(outcome, player, ply))
+            } else {
+                Self::search_after(
+                    board,
+                    rules,
+                    player,
+                    depth - 1,
+                    ply + 1,
+                    alpha,
+                    beta,
+                    deadline,
+                )
+            };
+            rules.undo(board, &undo);
+            let score = score?;
+            if score >= beta {
+                return Some(beta);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        Some(alpha)
+    }
+
+    /// Searches the position after the player has moved, scoring it from the
+    /// player's point of view whether the rules pass the turn or not.
+    #[allow(clippy::too_many_arguments)]
+    fn search_after(
+        board: &mut Board,
+        rules: &dyn Rules,
+        player: Player,
+        depth: usize,
+        ply: i32,
+        alpha: i32,
+        beta: i32,
+        deadline: Instant,
+    ) -> Option<i32> {
+        let next_player = rules.next_player(board, player);
+        if next_player == player {
+            Self::negamax(board, rules, player, depth, ply, alpha, beta, deadline)
+        } else {
+            Self::negamax(
+                board,
+                rules,
+                next_player,
+                depth,
+                ply,
+                -beta,
+                -alpha,
+                deadline,
+            )
+            .map(|score| -score)
+        }
+    }
+
+    /// Score of a finished game for the player, where the game ended `ply`
+    /// moves below the root.
+    fn score_outcome(outcome: Outcome, player: Player, ply: i32) -> i32 {
+        match outcome {
+            Outcome::Win(winner) if winner == player => WIN_SCORE - ply,
+            Outcome::Win(_) => -(WIN_SCORE - ply),
+            Outcome::Draw => 0,
+        }
+    }
+}
+
+impl FromStr for AI {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "random" => Ok(Self::Random),
+            "minimax" => Ok(Self::MiniMax),
+            "mcts" => Ok(Self::MonteCarlo),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown AI: {}", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Win(moves) => write!(f, "wins in {}", moves),
+            Self::Loss(moves) => write!(f, "loses in {}", moves),
+            Self::Score(score) => write!(f, "{:+}", score),
+        }
+    }
+}
+
+impl fmt::Display for AI {
+    /// Writes the name the AI is picked by on the command line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Random => "random",
+            Self::MiniMax => "minimax",
+            Self::MonteCarlo => "mcts",
         };
-        let marked_positions: HashSet<Coordinates> = marked_positions.keys().cloned().collect();
-        let remaining_positions: Vec<&Coordinates> = all_moves
-            .difference(&marked_positions)
-            .into_iter()
-            .collect();
-        let result = remaining_positions.choose(&mut rand::thread_rng()).unwrap();
-        *result.clone()
-    }
-
-    // fn minimax_mark(
-    //     marked_positions: &HashMap<Coordinates, Player>,
-    //     side: &Side,
-    // ) -> Coordinates {
-    //     // FIXME
-    //     Self::random_mark(marked_positions, side)
-    // }
+        write!(f, "{}", name)
+    }
+}
+
+/// A game on a single board in progress, as seen by the Monte Carlo tree search.
+#[derive(Clone)]
+struct ClassicPosition<'a> {
+    board: Board,
+    rules: &'a dyn Rules,
+    to_move: Player,
+    outcome: Option<Outcome>,
+}
+
+impl Playable for ClassicPosition<'_> {
+    type Move = Move;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        self.rules.legal_moves(&self.board, self.to_move)
+    }
+
+    fn play(&mut self, next: Move) {
+        self.rules.apply(&mut self.board, &next).unwrap();
+        self.outcome = self
+            .rules
+            .outcome(&self.board, &next.position, self.to_move);
+        self.to_move = self.rules.next_player(&self.board, self.to_move);
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
 }
@@ -0,0 +1,479 @@
+use super::ai::{Evaluation, AI, DEFAULT_THINK_TIME};
+use super::board::Board;
+use super::game::{key_direction, print_message};
+use super::grid::Grid;
+use super::record::GameRecord;
+use super::replay::{play_line, turns};
+use super::rules::{pick_move, Pick, Rules, Variant};
+use super::{Coordinates, Move, Outcome, Player};
+
+use crossterm::event::{read, Event, KeyCode};
+use crossterm::{cursor, execute, terminal};
+use std::fs;
+use std::io;
+use std::io::stdout;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// A move in the variation tree, and the moves tried after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// The move which leads to the node; `None` at the start of the game.
+    pub next: Option<Move>,
+    pub parent: Option<usize>,
+    /// The moves tried after this one, the one continuing its line first.
+    pub children: Vec<usize>,
+    /// What the engine makes of the position after the move, and the best
+    /// move it found there.
+    pub evaluation: Option<(Evaluation, Option<Move>)>,
+}
+
+/// Every line of play tried from the start of a game. The first move tried
+/// after another one continues its line, and the others branch off into
+/// variations, so that the main line of the game is never overwritten.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariationTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for VariationTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VariationTree {
+    /// The node at the start of the game.
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                next: None,
+                parent: None,
+                children: Vec::new(),
+                evaluation: None,
+            }],
+        }
+    }
+
+    /// The tree of the recorded game: its moves are the main line, and its
+    /// variations branch off it.
+    pub fn from_record(record: &GameRecord) -> Self {
+        let mut tree = Self::new();
+        tree.add_line(&record.moves);
+        for variation in &record.variations {
+            tree.add_line(variation);
+        }
+        tree
+    }
+
+    pub fn node(&self, node: usize) -> &Node {
+        &self.nodes[node]
+    }
+
+    /// Adds the move after the given node, unless it has been tried there
+    /// already, and returns its node.
+    pub fn add(&mut self, parent: usize, next: Move) -> usize {
+        let tried = self.nodes[parent]
+            .children
+            .iter()
+            .find(|child| self.nodes[**child].next == Some(next));
+        if let Some(child) = tried {
+            return *child;
+        }
+        self.nodes.push(Node {
+            next: Some(next),
+            parent: Some(parent),
+            children: Vec::new(),
+            evaluation: None,
+        });
+        let child = self.nodes.len() - 1;
+        self.nodes[parent].children.push(child);
+        child
+    }
+
+    /// Adds the moves from the start of the game and returns the node of the
+    /// last one.
+    pub fn add_line(&mut self, moves: &[Move]) -> usize {
+        moves
+            .iter()
+            .fold(Self::ROOT, |node, next| self.add(node, *next))
+    }
+
+    /// The moves from the start of the game up to the given node.
+    pub fn line(&self, mut node: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(next) = self.nodes[node].next {
+            moves.push(next);
+            node = self.nodes[node].parent.unwrap();
+        }
+        moves.reverse();
+        moves
+    }
+
+    /// The node at the end of the line the given node lies on.
+    pub fn last(&self, mut node: usize) -> usize {
+        while let Some(child) = self.nodes[node].children.first() {
+            node = *child;
+        }
+        node
+    }
+
+    /// The main line of the game, from its first move to its last.
+    pub fn main_line(&self) -> Vec<Move> {
+        self.line(self.last(Self::ROOT))
+    }
+
+    /// Every variation, each from the start of the game to its last move.
+    pub fn variations(&self) -> Vec<Vec<Move>> {
+        let main = self.last(Self::ROOT);
+        (0..self.nodes.len())
+            .filter(|node| *node != main && self.nodes[*node].children.is_empty())
+            .map(|node| self.line(node))
+            .collect()
+    }
+
+    /// The given node and every other move tried in its place.
+    pub fn siblings(&self, node: usize) -> Vec<usize> {
+        match self.nodes[node].parent {
+            Some(parent) => self.nodes[parent].children.clone(),
+            None => vec![node],
+        }
+    }
+
+    pub fn set_evaluation(&mut self, node: usize, evaluation: (Evaluation, Option<Move>)) {
+        self.nodes[node].evaluation = Some(evaluation);
+    }
+}
+
+/// A position in the tree: the board, the player to move and how the game
+/// ended, if it did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub board: Board,
+    pub to_move: Player,
+    /// The player who made the latest move, if any.
+    pub last_mover: Option<Player>,
+    pub outcome: Option<Outcome>,
+}
+
+/// Studies a game by going back to any of its moves and trying others, with
+/// the engine's verdict on every position.
+pub struct Analysis {
+    /// The game studied, which gives the board and the rules.
+    pub record: GameRecord,
+    pub tree: VariationTree,
+    pub grid: Grid,
+    /// How long the engine thinks about every position.
+    pub think_time: Duration,
+    /// File the analysis is saved to, along with the main line.
+    pub path: Option<PathBuf>,
+    /// The symbol a move places, which only changes under rules that allow
+    /// either symbol.
+    pub symbol: Player,
+    rules: Variant,
+    current: usize,
+    cursor: Coordinates,
+    /// The piece picked up to slide elsewhere.
+    selected: Option<Coordinates>,
+    message: Option<String>,
+}
+
+impl Analysis {
+    pub fn new(record: GameRecord) -> io::Result<Self> {
+        let rules = record.rules.parse()?;
+        let tree = VariationTree::from_record(&record);
+        let grid = Grid {
+            width: record.width,
+            height: record.height,
+            layers: record.depth,
+        };
+        Ok(Self {
+            record,
+            tree,
+            grid,
+            think_time: DEFAULT_THINK_TIME,
+            path: None,
+            symbol: Player::Cross,
+            rules,
+            current: VariationTree::ROOT,
+            cursor: Coordinates { x: 0, y: 0, z: 0 },
+            selected: None,
+            message: None,
+        })
+    }
+
+    /// The node of the position shown.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Shows the position at the given node, putting down any piece picked
+    /// up.
+    pub fn go_to(&mut self, node: usize) {
+        self.current = node;
+        self.selected = None;
+    }
+
+    /// Takes back the latest move.
+    pub fn back(&mut self) {
+        if let Some(parent) = self.tree.node(self.current).parent {
+            self.go_to(parent);
+        }
+    }
+
+    /// Plays the next move of the line shown.
+    pub fn forward(&mut self) {
+        if let Some(child) = self.tree.node(self.current).children.first() {
+            self.go_to(*child);
+        }
+    }
+
+    /// The piece picked up to slide elsewhere, if any.
+    pub fn selected(&self) -> Option<Coordinates> {
+        self.selected
+    }
+
+    /// Switches the symbol moves place, under rules which allow either.
+    pub fn toggle_symbol(&mut self) {
+        if self.rules.allows_any_symbol() {
+            self.symbol = self.symbol.opponent();
+        }
+    }
+
+    /// Switches to the move tried before or after the latest one in its
+    /// place, going round at either end.
+    pub fn switch_variation(&mut self, offset: isize) {
+        let siblings = self.tree.siblings(self.current);
+        let index = siblings
+            .iter()
+            .position(|sibling| *sibling == self.current)
+            .unwrap() as isize;
+        let count = siblings.len() as isize;
+        self.go_to(siblings[(index + offset).rem_euclid(count) as usize]);
+    }
+
+    /// The position after the moves up to the given node.
+    pub fn position(&self, node: usize) -> io::Result<Position> {
+        let line = self.tree.line(node);
+        let boards = play_line(&self.record, &line)?;
+        let turns = turns(&self.record, &line)?;
+        let outcome = line.last().and_then(|next| {
+            let mover = turns[line.len() - 1];
+            self.rules
+                .outcome(boards.last().unwrap(), &next.position, mover)
+        });
+        Ok(Position {
+            board: boards.into_iter().last().unwrap(),
+            to_move: turns[line.len()],
+            last_mover: line.len().checked_sub(1).map(|last| turns[last]),
+            outcome,
+        })
+    }
+
+    /// Plays a move on the given position for the player to move, as a new
+    /// variation unless the move has been tried there already, and returns
+    /// its node. Moves are picked as in a game: the symbol placed is
+    /// `symbol` under rules which let players place either, and once the
+    /// player gets to choose which of their pieces to move, as under sliding
+    /// rules, the first call picks up a piece and the second puts it down,
+    /// leaving the current node as it is in between.
+    pub fn play(&mut self, position: Coordinates) -> io::Result<usize> {
+        let Position {
+            board,
+            to_move,
+            outcome,
+            ..
+        } = self.position(self.current)?;
+        if outcome.is_some() {
+            return Err(io::Error::other("the game is over"));
+        }
+        let symbol = if self.rules.allows_any_symbol() {
+            self.symbol
+        } else {
+            to_move
+        };
+        let pick = pick_move(
+            &board,
+            &self.rules,
+            to_move,
+            symbol,
+            position,
+            self.selected,
+        )?;
+        let next = match pick {
+            Pick::Move(next) => next,
+            Pick::PickUp(piece) => {
+                self.selected = Some(piece);
+                return Ok(self.current);
+            }
+            Pick::PutBack(_) => {
+                self.selected = None;
+                return Ok(self.current);
+            }
+        };
+        let node = self.tree.add(self.current, next);
+        self.go_to(node);
+        Ok(node)
+    }
+
+    /// Has the engine judge the position at the given node, unless it has
+    /// already, the game is over there or it has more than two players.
+    pub fn evaluate(&mut self, node: usize) -> io::Result<()> {
+        if self.tree.node(node).evaluation.is_some()
+            || matches!(self.rules, Variant::Multiplayer { .. })
+        {
+            return Ok(());
+        }
+        let position = self.position(node)?;
+        if position.outcome.is_none() {
+            let deadline = Instant::now() + self.think_time;
+            let evaluation = AI::evaluate(&position.board, &self.rules, position.to_move, deadline);
+            self.tree.set_evaluation(node, evaluation);
+        }
+        Ok(())
+    }
+
+    /// The record of the game with its main line and every variation.
+    pub fn to_record(&self) -> GameRecord {
+        GameRecord {
+            moves: self.tree.main_line(),
+            variations: self.tree.variations(),
+            ..self.record.clone()
+        }
+    }
+
+    /// Shows the analysis until Esc is pressed. The cursor is moved around the
+    /// grid as in a game and Enter tries a move, or picks up the piece to
+    /// move, and Tab switches the symbol placed; the left and right arrows go
+    /// back and forth along a line, the up and down arrows switch between the
+    /// variations and `v` saves the analysis.
+    pub fn show(&mut self) -> crossterm::Result<()> {
+        loop {
+            self.evaluate(self.current)?;
+            self.draw()?;
+            self.message = None;
+            if let Event::Key(k) = read()? {
+                if let Some(direction) = key_direction(k.code) {
+                    self.cursor = self.record.board().cursor_step(self.cursor, &direction);
+                    continue;
+                }
+                match k.code {
+                    KeyCode::Enter => {
+                        if let Err(e) = self.play(self.cursor) {
+                            self.message = Some(e.to_string());
+                        }
+                    }
+                    KeyCode::Tab => self.toggle_symbol(),
+                    KeyCode::Left => self.back(),
+                    KeyCode::Right => self.forward(),
+                    KeyCode::Up => self.switch_variation(-1),
+                    KeyCode::Down => self.switch_variation(1),
+                    KeyCode::Home => self.go_to(VariationTree::ROOT),
+                    KeyCode::End => self.go_to(self.tree.last(self.current)),
+                    KeyCode::Char('v') => self.message = Some(self.save()),
+                    KeyCode::Esc => break,
+                    _ => {}
+                }
+            }
+        }
+        terminal::disable_raw_mode()?;
+        execute!(stdout(), cursor::MoveTo(0, self.grid.bottom() + 5))?;
+        println!();
+        Ok(())
+    }
+
+    /// Saves the analysis and tells how that went.
+    fn save(&self) -> String {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return "Nowhere to save the analysis".to_string(),
+        };
+        match fs::write(path, self.to_record().to_string()) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(e) => format!("Could not save: {}", e),
+        }
+    }
+
+    /// What the status lines say about the position at the current node: the
+    /// latest move and which variation it is, and how the game stands.
+    fn status(&self, position: &Position) -> (String, String) {
+        let node = self.tree.node(self.current);
+        let latest = match node.next {
+            None => "Start of the game".to_string(),
+            Some(next) => {
+                let siblings = self.tree.siblings(self.current);
+                let mover = position.last_mover.unwrap_or(next.symbol);
+                let mut latest = format!(
+                    "Move {}: {}",
+                    self.tree.line(self.current).len(),
+                    mover.to_char()
+                );
+                if next.symbol != mover {
+                    latest += &format!(" plays {}", next.symbol.to_char());
+                }
+                latest += &format!(" at {}", next.position);
+                if siblings.len() > 1 {
+                    let index = siblings.iter().position(|s| *s == self.current).unwrap();
+                    latest += &format!(", variation {} of {}", index + 1, siblings.len());
+                }
+                latest
+            }
+        };
+        let verdict = match (position.outcome, node.evaluation) {
+            (Some(Outcome::Win(player)), _) => format!("{} won", player.to_char()),
+            (Some(Outcome::Draw), _) => "Drawn".to_string(),
+            (None, Some((evaluation, best))) => {
+                let mut verdict = format!("{} to move: {}", position.to_move.to_char(), evaluation);
+                if let Some(best) = best {
+                    verdict += &format!(", best {}", best.position);
+                }
+                verdict
+            }
+            (None, None) => format!("{} to move", position.to_move.to_char()),
+        };
+        let verdict = if self.selected.is_some() {
+            format!("{}; pick an empty box to move to", verdict)
+        } else if self.rules.allows_any_symbol() && position.outcome.is_none() {
+            format!(
+                "{}; placing {} (Tab to switch)",
+                verdict,
+                self.symbol.to_char()
+            )
+        } else {
+            verdict
+        };
+        (latest, verdict)
+    }
+
+    /// Redraws the grid with the position at the current node, the latest
+    /// move highlighted, and the status lines below it.
+    fn draw(&mut self) -> crossterm::Result<()> {
+        let position = self.position(self.current)?;
+        self.grid.draw()?.draw_position(&position.board)?;
+        if let Some(next) = self.tree.node(self.current).next {
+            if let Some(player) = position.board.get(&next.position) {
+                self.grid.highlight_at(next.position, player.to_char())?;
+            }
+        }
+        if let Some(selected) = self.selected {
+            if let Some(player) = position.board.get(&selected) {
+                self.grid.highlight_at(selected, player.to_char())?;
+            }
+        }
+        let (latest, verdict) = self.status(&position);
+        let row = self.grid.bottom() + 1;
+        print_message(row, &latest)?;
+        print_message(row + 1, &verdict)?;
+        let help = "wasd: cursor  Enter: try move  Left/Right: back/forward  \
+                    Up/Down: variation  v: save  Esc: quit";
+        print_message(row + 2, self.message.as_deref().unwrap_or(help))?;
+        let screen_coords = self.grid.screen_coords(&self.cursor);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,696 @@
+use super::{Coordinates, Direction, Move, Player, Side};
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+/// Direction vectors of the lines which can win a game: horizontal, vertical
+/// and the two diagonals.
+pub const LINE_DIRECTIONS: [Coordinates; 4] = [
+    Coordinates { x: 1, y: 0, z: 0 },
+    Coordinates { x: 0, y: 1, z: 0 },
+    Coordinates { x: 1, y: 1, z: 0 },
+    Coordinates { x: 1, y: -1, z: 0 },
+];
+
+/// Direction vectors of the lines which can win a game on a board of several
+/// layers: the lines within a layer, the columns through the layers and the
+/// diagonals which cross the layers.
+pub const SPACE_DIRECTIONS: [Coordinates; 13] = [
+    Coordinates { x: 1, y: 0, z: 0 },
+    Coordinates { x: 0, y: 1, z: 0 },
+    Coordinates { x: 1, y: 1, z: 0 },
+    Coordinates { x: 1, y: -1, z: 0 },
+    Coordinates { x: 0, y: 0, z: 1 },
+    Coordinates { x: 1, y: 0, z: 1 },
+    Coordinates { x: 1, y: 0, z: -1 },
+    Coordinates { x: 0, y: 1, z: 1 },
+    Coordinates { x: 0, y: 1, z: -1 },
+    Coordinates { x: 1, y: 1, z: 1 },
+    Coordinates { x: 1, y: 1, z: -1 },
+    Coordinates { x: 1, y: -1, z: 1 },
+    Coordinates { x: 1, y: -1, z: -1 },
+];
+
+/// One of the eight ways a square board can be rotated or reflected onto itself.
+/// Boards which are not square only map onto themselves under the identity,
+/// the half turn and the two flips along the axes. On boards of several layers
+/// every layer is transformed alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// The symmetries which keep a board of the given dimensions in place.
+    pub fn of(width: u16, height: u16) -> &'static [Symmetry] {
+        if width == height {
+            &Self::ALL
+        } else {
+            &[
+                Symmetry::Identity,
+                Symmetry::Rotate180,
+                Symmetry::FlipHorizontal,
+                Symmetry::FlipVertical,
+            ]
+        }
+    }
+
+    /// Maps a position onto where it lands after applying this symmetry to a
+    /// board of the given dimensions. Rotations are clockwise as seen on the
+    /// screen and only make sense for square boards.
+    pub fn apply(&self, position: Coordinates, width: u16, height: u16) -> Coordinates {
+        let last_x = width as i16 - 1;
+        let last_y = height as i16 - 1;
+        let Coordinates { x, y, z } = position;
+        let (x, y) = match self {
+            Self::Identity => (x, y),
+            Self::Rotate90 => (last_y - y, x),
+            Self::Rotate180 => (last_x - x, last_y - y),
+            Self::Rotate270 => (y, last_x - x),
+            Self::FlipHorizontal => (last_x - x, y),
+            Self::FlipVertical => (x, last_y - y),
+            Self::FlipDiagonal => (y, x),
+            Self::FlipAntiDiagonal => (last_y - y, last_x - x),
+        };
+        Coordinates { x, y, z }
+    }
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Self::Rotate90 => Self::Rotate270,
+            Self::Rotate270 => Self::Rotate90,
+            // Every other symmetry is its own inverse.
+            _ => *self,
+        }
+    }
+}
+
+/// The Board keeps track of which player has marked which position, independent
+/// of how the marks are drawn on the screen. A player wins by getting
+/// `win_length` marks in a row, so the board describes any m,n,k-game. Boards
+/// with a `depth` of more than one stack that many layers into a cube, like the
+/// 4x4x4 board of Qubic, where lines may also run through the layers.
+///
+/// On boards with `gravity` marks fall to the lowest empty row of their
+/// column, as in Connect Four, so only those positions may be marked.
+///
+/// Blocked positions are holes in the board which can never be marked, so
+/// that boards of other shapes, such as a plus, can be read from a map with
+/// `from_map`.
+///
+/// On a `torus` the board wraps around: a line running off one edge carries on
+/// from the opposite edge, so that on a 3x3 torus the broken diagonals win too.
+///
+/// Alongside the marks it incrementally maintains a Zobrist hash of the position
+/// as seen through each of its symmetries, so that AIs and analysis tools can
+/// cheaply key caches on either the exact or the canonical position.
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub width: u16,
+    pub height: u16,
+    /// Number of layers; flat boards have one.
+    pub depth: u16,
+    pub win_length: u16,
+    /// Whether marks drop to the bottom of their column; only set this on an
+    /// empty board.
+    pub gravity: bool,
+    /// Whether lines wrap around the edges of every layer.
+    pub torus: bool,
+    blocked: HashSet<Coordinates>,
+    marked_positions: HashMap<Coordinates, Player>,
+    /// The marked positions in the order they were marked, oldest first.
+    placed: Vec<Coordinates>,
+    hashes: [u64; 8],
+}
+
+/// Boards are equal when they have the same dimensions and marks, no matter
+/// in which order the marks were placed.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.depth == other.depth
+            && self.win_length == other.win_length
+            && self.gravity == other.gravity
+            && self.torus == other.torus
+            && self.blocked == other.blocked
+            && self.marked_positions == other.marked_positions
+    }
+}
+
+/// A move played with `Board::play`, with what it takes to take it back:
+/// where the piece it lifted came in the order of the marks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Undo {
+    pub next: Move,
+    lifted: Option<usize>,
+}
+
+impl Board {
+    /// A square board where a player needs to fill a whole row, column or
+    /// diagonal to win.
+    pub fn from(side: Side) -> Self {
+        let Side(side) = side;
+        Self::new(side, side, side)
+    }
+
+    pub fn new(width: u16, height: u16, win_length: u16) -> Self {
+        Self::layered(width, height, 1, win_length)
+    }
+
+    /// A board of `depth` layers stacked on top of each other.
+    pub fn layered(width: u16, height: u16, depth: u16, win_length: u16) -> Self {
+        let mut board = Self {
+            width,
+            height,
+            depth,
+            win_length,
+            gravity: false,
+            torus: false,
+            blocked: HashSet::new(),
+            marked_positions: HashMap::new(),
+            placed: Vec::new(),
+            hashes: [0; 8],
+        };
+        board.marked_positions.reserve(board.size());
+        board.placed.reserve(board.size());
+        board
+    }
+
+    /// Reads a board from a text map with a line for every row: `.` for an
+    /// open position and `#` or a space for a blocked one. Rows shorter than
+    /// the widest one are blocked past their end. A first line such as `win 4`
+    /// sets the win length, which is otherwise the shorter side of the board.
+    pub fn from_map(map: &str) -> io::Result<Self> {
+        let mut rows: Vec<&str> = map.lines().map(str::trim_end).collect();
+        let mut win_length = None;
+        if let Some(value) = rows.first().and_then(|row| row.strip_prefix("win ")) {
+            let value = value
+                .trim()
+                .parse::<u16>()
+                .map_err(|_| invalid_map(&format!("invalid win length: {}", value)))?;
+            win_length = Some(value);
+            rows.remove(0);
+        }
+        while rows.last() == Some(&"") {
+            rows.pop();
+        }
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as u16;
+        let height = rows.len() as u16;
+        let win_length = win_length.unwrap_or_else(|| width.min(height));
+        if width == 0 || win_length == 0 || win_length > width.max(height) {
+            return Err(invalid_map("the map has no room for a line"));
+        }
+        let mut board = Self::new(width, height, win_length);
+        for y in 0..(height as i16) {
+            let mut row = rows[y as usize].chars();
+            for x in 0..(width as i16) {
+                match row.next() {
+                    Some('.') => {}
+                    Some('#') | Some(' ') | None => board.block(Coordinates { x, y, z: 0 })?,
+                    Some(c) => return Err(invalid_map(&format!("unknown map symbol: {}", c))),
+                }
+            }
+        }
+        Ok(board)
+    }
+
+    /// Number of positions on the board which are not blocked.
+    pub fn size(&self) -> usize {
+        usize::from(self.width) * usize::from(self.height) * usize::from(self.depth)
+            - self.blocked.len()
+    }
+
+    pub fn marked_positions(&self) -> &HashMap<Coordinates, Player> {
+        &self.marked_positions
+    }
+
+    pub fn get(&self, position: &Coordinates) -> Option<&Player> {
+        self.marked_positions.get(position)
+    }
+
+    /// Whether the position is on the board and not blocked.
+    pub fn contains(&self, position: &Coordinates) -> bool {
+        (0..self.width as i16).contains(&position.x)
+            && (0..self.height as i16).contains(&position.y)
+            && (0..self.depth as i16).contains(&position.z)
+            && !self.is_blocked(position)
+    }
+
+    pub fn is_blocked(&self, position: &Coordinates) -> bool {
+        !self.blocked.is_empty() && self.blocked.contains(position)
+    }
+
+    /// Makes the position a hole in the board which can never be marked.
+    pub fn block(&mut self, position: Coordinates) -> io::Result<()> {
+        if !self.contains(&position) {
+            return Err(io::Error::other(
+                "position coordinates are out of bounds from the grid area",
+            ));
+        }
+        if self.marked_positions.contains_key(&position) {
+            return Err(io::Error::other("the position has already been marked"));
+        }
+        self.blocked.insert(position);
+        Ok(())
+    }
+
+    /// Every blocked position, layer by layer in row-major order.
+    pub fn blocked_positions(&self) -> Vec<Coordinates> {
+        let mut blocked: Vec<Coordinates> = self.blocked.iter().copied().collect();
+        blocked.sort_by_key(|position| (position.z, position.y, position.x));
+        blocked
+    }
+
+    /// The position a step off an edge of a torus comes back in at, or the
+    /// position itself on other boards. Lines never wrap through the layers.
+    pub fn wrap(&self, position: Coordinates) -> Coordinates {
+        if !self.torus {
+            return position;
+        }
+        Coordinates {
+            x: position.x.rem_euclid(self.width as i16),
+            y: position.y.rem_euclid(self.height as i16),
+            z: position.z,
+        }
+    }
+
+    /// Where a cursor on the given position lands when moved a step in the
+    /// direction: on the next position which is not blocked, coming back in
+    /// at the opposite edge of a torus and otherwise stopping at the edges.
+    /// It stays put if there is no such position.
+    pub fn cursor_step(&self, from: Coordinates, direction: &Direction) -> Coordinates {
+        let step = |position: Coordinates| {
+            let next = self.wrap(position + direction.get_relative_coords());
+            Coordinates {
+                x: next.x.clamp(0, self.width as i16 - 1),
+                y: next.y.clamp(0, self.height as i16 - 1),
+                z: next.z.clamp(0, self.depth as i16 - 1),
+            }
+        };
+        let mut position = step(from);
+        while self.is_blocked(&position) {
+            let next = step(position);
+            if next == position || next == from {
+                return from;
+            }
+            position = next;
+        }
+        position
+    }
+
+    /// Direction vectors of the lines which can win a game on this board.
+    pub fn line_directions(&self) -> &'static [Coordinates] {
+        if self.depth > 1 {
+            &SPACE_DIRECTIONS
+        } else {
+            &LINE_DIRECTIONS
+        }
+    }
+
+    /// Every line of `win_length` positions on the board, such as the 8 lines
+    /// of the classic board, the 76 lines of the 4x4x4 cube or the 12 lines of
+    /// the 3x3 torus.
+    pub fn lines(&self) -> Vec<Vec<Coordinates>> {
+        let mut lines = Vec::new();
+        for start in self.positions() {
+            for direction in self.line_directions() {
+                let mut line = Vec::with_capacity(usize::from(self.win_length));
+                let mut position = start;
+                while line.len() < usize::from(self.win_length)
+                    && self.contains(&position)
+                    && !line.contains(&position)
+                {
+                    line.push(position);
+                    position = self.wrap(position + *direction);
+                }
+                if line.len() == usize::from(self.win_length) {
+                    lines.push(line);
+                }
+            }
+        }
+        if self.torus {
+            // A line which wraps all the way round is found from each of its
+            // positions.
+            let mut seen = Vec::with_capacity(lines.len());
+            lines.retain(|line| {
+                let mut key = line.clone();
+                key.sort_by_key(|position| (position.z, position.y, position.x));
+                if seen.contains(&key) {
+                    return false;
+                }
+                seen.push(key);
+                true
+            });
+        }
+        lines
+    }
+
+    /// The symmetries which map the position onto an equivalent one. Under
+    /// gravity only the flip along the columns keeps the bottom row in place,
+    /// and on boards with holes only those which map the holes onto each other
+    /// keep its shape.
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        let symmetries: &[Symmetry] = if self.gravity {
+            &[Symmetry::Identity, Symmetry::FlipHorizontal]
+        } else {
+            Symmetry::of(self.width, self.height)
+        };
+        symmetries
+            .iter()
+            .filter(|symmetry| {
+                self.blocked.iter().all(|position| {
+                    self.blocked
+                        .contains(&symmetry.apply(*position, self.width, self.height))
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Places the player's mark at the given position.
+    pub fn mark(&mut self, position: Coordinates, player: Player) -> io::Result<()> {
+        if self.is_blocked(&position) {
+            return Err(io::Error::other("the position is blocked"));
+        }
+        if !self.contains(&position) {
+            return Err(io::Error::other(
+                "position coordinates are out of bounds from the grid area",
+            ));
+        }
+        if self.marked_positions.contains_key(&position) {
+            return Err(io::Error::other("the position has already been marked"));
+        }
+        if self.gravity && self.drop_position(&position) != Some(position) {
+            return Err(io::Error::other("the position below is still empty"));
+        }
+        self.marked_positions.insert(position, player);
+        self.placed.push(position);
+        self.toggle_hashes(position, player);
+        Ok(())
+    }
+
+    /// Plays a move: places its symbol, after lifting the piece off its old
+    /// position if it slides. Returns what `undo` needs to take it back.
+    pub fn play(&mut self, next: &Move) -> io::Result<Undo> {
+        let mut lifted = None;
+        if let Some(from) = next.from {
+            if self.get(&from) != Some(&next.symbol) {
+                return Err(io::Error::other("there is no piece to move there"));
+            }
+            let index = self.age(&from);
+            self.unmark(&from);
+            if let Err(e) = self.mark(next.position, next.symbol) {
+                self.restore(from, next.symbol, index);
+                return Err(e);
+            }
+            lifted = Some(index);
+        } else {
+            self.mark(next.position, next.symbol)?;
+        }
+        Ok(Undo {
+            next: *next,
+            lifted,
+        })
+    }
+
+    /// Takes back the latest move played with `play`.
+    pub fn undo(&mut self, undo: &Undo) {
+        let next = &undo.next;
+        self.unmark(&next.position);
+        if let (Some(from), Some(index)) = (next.from, undo.lifted) {
+            self.restore(from, next.symbol, index);
+        }
+    }
+
+    /// Where the mark at the given position comes in the order the marks were
+    /// placed in, oldest first.
+    fn age(&self, position: &Coordinates) -> usize {
+        self.placed
+            .iter()
+            .position(|placed| placed == position)
+            .expect("the position is marked")
+    }
+
+    /// Puts a lifted piece back where it was in the order of the marks,
+    /// without checking whether it may be placed there, so that the order of
+    /// each player's marks is as it was before the move.
+    fn restore(&mut self, position: Coordinates, player: Player, index: usize) {
+        self.marked_positions.insert(position, player);
+        self.placed.insert(index, position);
+        self.toggle_hashes(position, player);
+    }
+
+    /// Removes the mark at the given position, returning the player it belonged to.
+    pub fn unmark(&mut self, position: &Coordinates) -> Option<Player> {
+        let player = self.marked_positions.remove(position)?;
+        self.placed.retain(|placed| placed != position);
+        self.toggle_hashes(*position, player);
+        Some(player)
+    }
+
+    /// Turns every cross into a zero and the other way around, as when the
+    /// players swap sides. The marks keep the order they were placed in.
+    pub fn swap_sides(&mut self) {
+        let marks: Vec<(Coordinates, Player)> = self
+            .marked_positions
+            .iter()
+            .map(|(position, player)| (*position, *player))
+            .collect();
+        for (position, player) in marks {
+            self.toggle_hashes(position, player);
+            self.toggle_hashes(position, player.opponent());
+            self.marked_positions.insert(position, player.opponent());
+        }
+    }
+
+    /// The player's marks in the order they were placed, oldest first.
+    pub fn marks_of(&self, player: Player) -> Vec<Coordinates> {
+        self.placed
+            .iter()
+            .filter(|position| self.marked_positions.get(position) == Some(&player))
+            .copied()
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.marked_positions.len() == self.size()
+    }
+
+    /// Every position on the board which is not blocked, layer by layer in
+    /// row-major order.
+    pub fn positions(&self) -> Vec<Coordinates> {
+        let mut positions = Vec::with_capacity(self.size());
+        for z in 0..(self.depth as i16) {
+            for y in 0..(self.height as i16) {
+                for x in 0..(self.width as i16) {
+                    let position = Coordinates { x, y, z };
+                    if !self.is_blocked(&position) {
+                        positions.push(position);
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    /// All positions that have not been marked yet, layer by layer in
+    /// row-major order.
+    pub fn empty_positions(&self) -> Vec<Coordinates> {
+        self.positions()
+            .into_iter()
+            .filter(|position| !self.marked_positions.contains_key(position))
+            .collect()
+    }
+
+    /// The positions the next mark may be placed on: the lowest empty position
+    /// of every column under gravity, or else every empty position.
+    pub fn legal_moves(&self) -> Vec<Coordinates> {
+        if !self.gravity {
+            return self.empty_positions();
+        }
+        let mut moves = Vec::with_capacity(usize::from(self.width) * usize::from(self.depth));
+        for z in 0..(self.depth as i16) {
+            for x in 0..(self.width as i16) {
+                moves.extend(self.drop_position(&Coordinates { x, y: 0, z }));
+            }
+        }
+        moves
+    }
+
+    /// Where a mark dropped into the top of the column of the given position
+    /// comes to rest, on top of the first mark or hole below it, or `None` if
+    /// the column is full.
+    pub fn drop_position(&self, position: &Coordinates) -> Option<Coordinates> {
+        let is_free = |position: &Coordinates| {
+            self.contains(position) && !self.marked_positions.contains_key(position)
+        };
+        let mut resting = Coordinates { y: 0, ..*position };
+        if !is_free(&resting) {
+            return None;
+        }
+        loop {
+            let below = Coordinates {
+                y: resting.y + 1,
+                ..resting
+            };
+            if !is_free(&below) {
+                return Some(resting);
+            }
+            resting = below;
+        }
+    }
+
+    /// Checks whether the mark at the given position is part of a line of
+    /// `win_length` marks of the player. Only lines through that position are
+    /// looked at, which makes this the cheap check to run after every move.
+    pub fn completes_line(&self, position: &Coordinates, player: &Player) -> bool {
+        if self.get(position) != Some(player) {
+            return false;
+        }
+        self.line_directions().iter().any(|direction| {
+            let backwards = Coordinates {
+                x: -direction.x,
+                y: -direction.y,
+                z: -direction.z,
+            };
+            let (forward, past) = self.count_in_direction(position, direction, player);
+            let length = if past == *position {
+                // The marks run all the way round the torus.
+                1 + forward
+            } else {
+                1 + forward + self.count_in_direction(position, &backwards, player).0
+            };
+            length >= self.win_length
+        })
+    }
+
+    /// Checks whether the player has a line of `win_length` marks anywhere on
+    /// the board.
+    pub fn has_line(&self, player: &Player) -> bool {
+        self.marked_positions
+            .iter()
+            .any(|(position, marker)| marker == player && self.completes_line(position, player))
+    }
+
+    /// Number of consecutive marks of the player next to the position, walking
+    /// in the given direction, along with the first position past them. On a
+    /// torus the walk stops once it comes back round to the position.
+    fn count_in_direction(
+        &self,
+        position: &Coordinates,
+        direction: &Coordinates,
+        player: &Player,
+    ) -> (u16, Coordinates) {
+        let mut count = 0;
+        let mut next = self.wrap(*position + *direction);
+        while next != *position && self.get(&next) == Some(player) {
+            count += 1;
+            next = self.wrap(next + *direction);
+        }
+        (count, next)
+    }
+
+    /// Zobrist hash of the position exactly as it is on the board.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash_under(Symmetry::Identity)
+    }
+
+    /// Zobrist hash of the position after applying the given symmetry to it.
+    pub fn hash_under(&self, symmetry: Symmetry) -> u64 {
+        self.hashes[symmetry as usize]
+    }
+
+    /// The symmetry which takes this position to its canonical form, along with
+    /// the hash of that form. Positions that are rotations or reflections of each
+    /// other share the same canonical hash.
+    pub fn canonical_symmetry(&self) -> (Symmetry, u64) {
+        self.symmetries()
+            .iter()
+            .map(|symmetry| (*symmetry, self.hash_under(*symmetry)))
+            .min_by_key(|(_, hash)| *hash)
+            .unwrap()
+    }
+
+    pub fn canonical_hash(&self) -> u64 {
+        let (_, hash) = self.canonical_symmetry();
+        hash
+    }
+
+    /// Reduces the position to its canonical form. The returned symmetry maps
+    /// positions on this board to positions on the canonical board; use its
+    /// inverse to map a move found on the canonical board back.
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        let (symmetry, _) = self.canonical_symmetry();
+        (self.transformed(symmetry), symmetry)
+    }
+
+    /// A copy of the board with the given symmetry applied to every mark.
+    pub fn transformed(&self, symmetry: Symmetry) -> Board {
+        let mut board = Board::layered(self.width, self.height, self.depth, self.win_length);
+        for position in &self.blocked {
+            board
+                .block(symmetry.apply(*position, self.width, self.height))
+                .expect("symmetries keep positions on the board");
+        }
+        for position in &self.placed {
+            let player = self.marked_positions[position];
+            board
+                .mark(symmetry.apply(*position, self.width, self.height), player)
+                .expect("symmetries keep positions on the board");
+        }
+        // Set only now, since the marks may land in an order which gravity
+        // would not allow.
+        board.gravity = self.gravity;
+        board.torus = self.torus;
+        board
+    }
+
+    fn toggle_hashes(&mut self, position: Coordinates, player: Player) {
+        for symmetry in Symmetry::of(self.width, self.height) {
+            let key = zobrist_key(symmetry.apply(position, self.width, self.height), player);
+            self.hashes[*symmetry as usize] ^= key;
+        }
+    }
+}
+
+/// Pseudo-random key for a player's mark on a position. Keys are derived from
+/// the coordinates with SplitMix64 rather than drawn into a table, so boards of
+/// any size share the same keys and need no setup.
+fn zobrist_key(position: Coordinates, player: Player) -> u64 {
+    let seed = ((position.z as u16 as u64) << 48)
+        | ((position.x as u16 as u64) << 32)
+        | ((position.y as u16 as u64) << 16)
+        | (player as u64 + 1);
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn invalid_map(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
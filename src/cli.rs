@@ -0,0 +1,451 @@
+use super::ai::AI;
+use super::clock::TimeControl;
+use super::game::Seat;
+use super::replay::DEFAULT_SPEED;
+use super::rules::{Rules, Variant};
+use super::stats::{check_profile, DEFAULT_PROFILE};
+use super::swap::Swap;
+use super::Player;
+
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What the program has been asked to do, as parsed from its command line.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Play a game in the terminal.
+    Play(GameOptions),
+    /// Set up a position on the board the options call for, and play or
+    /// analyze it.
+    Edit(GameOptions),
+    /// Exhaustively solve an m,n,k-game and print statistics about it.
+    Solve {
+        width: u16,
+        height: u16,
+        win_length: u16,
+    },
+    /// Print the statistics of a player profile.
+    Stats { profile: String },
+    /// Print the ratings of every player profile and AI.
+    Leaderboard,
+    /// Step through a saved game, or play it back at the given speed.
+    Replay { file: PathBuf, speed: Duration },
+    /// Study a saved game by trying other moves, with the engine thinking
+    /// for the given time about every position.
+    Analyze {
+        file: PathBuf,
+        think_time: Option<Duration>,
+    },
+}
+
+impl Command {
+    /// Parses the program arguments, not including the program name.
+    pub fn parse<I>(args: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter().peekable();
+        match args.peek().map(String::as_str) {
+            Some("solve") => {
+                args.next();
+                let config = args
+                    .next()
+                    .ok_or_else(|| invalid_input("solve needs a board such as 3x3x3"))?;
+                let (width, height, win_length) = parse_board_config(&config)?;
+                Ok(Self::Solve {
+                    width,
+                    height,
+                    win_length,
+                })
+            }
+            Some("stats") => {
+                args.next();
+                let profile = match (args.next(), args.next()) {
+                    (None, _) => DEFAULT_PROFILE.to_string(),
+                    (Some(flag), value) if flag == "--profile" => flag_value(&flag, value)?,
+                    (Some(arg), _) => {
+                        return Err(invalid_input(&format!("unknown argument: {}", arg)))
+                    }
+                };
+                if let Some(arg) = args.next() {
+                    return Err(invalid_input(&format!("unknown argument: {}", arg)));
+                }
+                check_profile(&profile)?;
+                Ok(Self::Stats { profile })
+            }
+            Some("edit") => {
+                args.next();
+                let options = GameOptions::parse(args)?;
+                if !matches!(options.mode, Mode::Classic | Mode::Qubic) {
+                    return Err(invalid_input("edit only works in classic and qubic modes"));
+                }
+                if options.swap.is_some() {
+                    return Err(invalid_input("edit does not work with --swap"));
+                }
+                if options.seats.as_ref().is_some_and(|seats| seats.len() > 2) {
+                    return Err(invalid_input("edit only sets up games of two players"));
+                }
+                if options.best_of.is_some() {
+                    return Err(invalid_input("edit plays a single game"));
+                }
+                Ok(Self::Edit(options))
+            }
+            Some("replay") => {
+                args.next();
+                let file = args
+                    .next()
+                    .ok_or_else(|| invalid_input("replay needs a saved game"))?;
+                let mut speed = DEFAULT_SPEED;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--speed" => speed = Duration::from_millis(flag_value(&arg, args.next())?),
+                        _ => return Err(invalid_input(&format!("unknown argument: {}", arg))),
+                    }
+                }
+                Ok(Self::Replay {
+                    file: file.into(),
+                    speed,
+                })
+            }
+            Some("analyze") => {
+                args.next();
+                let file = args
+                    .next()
+                    .ok_or_else(|| invalid_input("analyze needs a saved game"))?;
+                let mut think_time = None;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--ai-time" => {
+                            let millis = flag_value(&arg, args.next())?;
+                            think_time = Some(Duration::from_millis(millis));
+                        }
+                        _ => return Err(invalid_input(&format!("unknown argument: {}", arg))),
+                    }
+                }
+                Ok(Self::Analyze {
+                    file: file.into(),
+                    think_time,
+                })
+            }
+            Some("leaderboard") => {
+                args.next();
+                match args.next() {
+                    Some(arg) => Err(invalid_input(&format!("unknown argument: {}", arg))),
+                    None => Ok(Self::Leaderboard),
+                }
+            }
+            _ => Ok(Self::Play(GameOptions::parse(args)?)),
+        }
+    }
+}
+
+/// Which game to play in the terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// The classic game on a single board.
+    #[default]
+    Classic,
+    /// Nine boards in one, where every move sends the opponent to a board.
+    Ultimate,
+    /// Qubic: a 4x4x4 cube where lines may run through the layers.
+    Qubic,
+    /// Quantum tic-tac-toe, where marks sit in two boxes until they collapse.
+    Quantum,
+}
+
+impl FromStr for Mode {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "classic" => Ok(Self::Classic),
+            "ultimate" => Ok(Self::Ultimate),
+            "qubic" => Ok(Self::Qubic),
+            "quantum" => Ok(Self::Quantum),
+            _ => Err(invalid_input(&format!("unknown mode: {}", s))),
+        }
+    }
+}
+
+/// Settings for a game played in the terminal.
+#[derive(Debug, Default, PartialEq)]
+pub struct GameOptions {
+    /// Seed for the AI's random number generator; a random seed is picked when
+    /// none is given.
+    pub seed: Option<u64>,
+    /// File to save the game record to once the game is over.
+    pub save: Option<PathBuf>,
+    pub ai: AI,
+    /// Whether to play the AI whose rating is closest to the player's rather
+    /// than the one picked.
+    pub auto_ai: bool,
+    /// The most time the AI may think about a single move.
+    pub ai_time: Option<Duration>,
+    pub clock: Option<TimeControl>,
+    pub rules: Variant,
+    pub mode: Mode,
+    /// Width, height and win length of a classic board other than 3x3.
+    pub board: Option<(u16, u16, u16)>,
+    /// Whether marks drop to the bottom of their column.
+    pub gravity: bool,
+    /// Whether lines wrap around the edges of the board.
+    pub torus: bool,
+    /// File with a map of a board with blocked boxes, as read by
+    /// `Board::from_map`.
+    pub map: Option<PathBuf>,
+    /// Swap rule to open the game with.
+    pub swap: Option<Swap>,
+    /// Whether the AI places the opening stones under the swap rule rather
+    /// than the player.
+    pub ai_opens: bool,
+    /// Who moves for each player, in turn order, unless the player plays
+    /// crosses against the AI.
+    pub seats: Option<Vec<Seat>>,
+    /// Number of games in a match, where the players take turns moving
+    /// first; a single game is played unless told otherwise.
+    pub best_of: Option<u16>,
+    /// Profile whose statistics games against the AI are counted in; the
+    /// default profile unless told otherwise.
+    pub profile: Option<String>,
+}
+
+impl GameOptions {
+    /// Parses the options of a game, as given on the command line.
+    pub fn parse<I>(args: I) -> io::Result<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => options.seed = Some(flag_value(&arg, args.next())?),
+                "--save" => options.save = Some(flag_value(&arg, args.next())?),
+                "--ai" => match args.next() {
+                    Some(value) if value == "auto" => options.auto_ai = true,
+                    value => options.ai = flag_value(&arg, value)?,
+                },
+                "--ai-time" => {
+                    let millis = flag_value(&arg, args.next())?;
+                    options.ai_time = Some(Duration::from_millis(millis));
+                }
+                "--clock" => options.clock = Some(flag_value(&arg, args.next())?),
+                "--rules" => options.rules = flag_value(&arg, args.next())?,
+                "--mode" => options.mode = flag_value(&arg, args.next())?,
+                "--board" => {
+                    let config: String = flag_value(&arg, args.next())?;
+                    options.board = Some(parse_board_config(&config)?);
+                }
+                "--gravity" => options.gravity = true,
+                "--torus" => options.torus = true,
+                "--map" => options.map = Some(flag_value(&arg, args.next())?),
+                "--swap" => options.swap = Some(flag_value(&arg, args.next())?),
+                "--ai-opens" => options.ai_opens = true,
+                "--best-of" => match flag_value(&arg, args.next())? {
+                    0 => return Err(invalid_input("--best-of needs at least one game")),
+                    games => options.best_of = Some(games),
+                },
+                "--profile" => {
+                    let profile: String = flag_value(&arg, args.next())?;
+                    check_profile(&profile)?;
+                    options.profile = Some(profile);
+                }
+                "--players" => {
+                    let seats: String = flag_value(&arg, args.next())?;
+                    options.seats = Some(parse_seats(&seats)?);
+                }
+                _ => return Err(invalid_input(&format!("unknown argument: {}", arg))),
+            }
+        }
+        if options.mode == Mode::Ultimate && !options.ai.supports_ultimate() {
+            return Err(invalid_input(&format!(
+                "{:?} cannot play ultimate tic-tac-toe",
+                options.ai
+            )));
+        }
+        if options.mode == Mode::Quantum && !options.ai.supports_quantum() {
+            return Err(invalid_input(&format!(
+                "{:?} cannot play quantum tic-tac-toe",
+                options.ai
+            )));
+        }
+        if options.board.is_some() && options.mode != Mode::Classic {
+            return Err(invalid_input("--board only works in classic mode"));
+        }
+        if options.map.is_some() && options.mode != Mode::Classic {
+            return Err(invalid_input("--map only works in classic mode"));
+        }
+        if options.map.is_some() && options.board.is_some() {
+            return Err(invalid_input("--map already gives the board"));
+        }
+        if options.gravity && options.mode == Mode::Ultimate {
+            return Err(invalid_input(
+                "--gravity does not work with ultimate tic-tac-toe",
+            ));
+        }
+        // Ultimate and quantum tic-tac-toe are played by their own rules,
+        // untimed and unrecorded.
+        let unsupported = [
+            ("--rules", options.rules != Variant::default()),
+            ("--clock", options.clock.is_some()),
+            ("--save", options.save.is_some()),
+        ];
+        for (flag, given) in unsupported {
+            let mode = match options.mode {
+                Mode::Ultimate => "ultimate",
+                Mode::Quantum => "quantum",
+                Mode::Classic | Mode::Qubic => continue,
+            };
+            if given {
+                return Err(invalid_input(&format!(
+                    "{} does not work with {} tic-tac-toe",
+                    flag, mode
+                )));
+            }
+        }
+        if options.gravity && options.mode == Mode::Quantum {
+            return Err(invalid_input(
+                "--gravity does not work with quantum tic-tac-toe",
+            ));
+        }
+        if options.torus && matches!(options.mode, Mode::Ultimate | Mode::Quantum) {
+            return Err(invalid_input(
+                "--torus only works in classic and qubic modes",
+            ));
+        }
+        if options.gravity
+            && matches!(
+                options.rules,
+                Variant::Sliding { .. } | Variant::Vanishing { .. }
+            )
+        {
+            return Err(invalid_input(
+                "--gravity does not work with rules which take marks off the board",
+            ));
+        }
+        if options.swap.is_some() && matches!(options.mode, Mode::Ultimate | Mode::Quantum) {
+            return Err(invalid_input(
+                "--swap only works in classic and qubic modes",
+            ));
+        }
+        if options.swap.is_some() && options.rules.allows_any_symbol() {
+            return Err(invalid_input(
+                "--swap does not work with rules which let players place either symbol",
+            ));
+        }
+        if options.ai_opens && options.swap.is_none() {
+            return Err(invalid_input("--ai-opens only works with --swap"));
+        }
+        if options.best_of.is_some() && matches!(options.mode, Mode::Ultimate | Mode::Quantum) {
+            return Err(invalid_input(
+                "--best-of only works in classic and qubic modes",
+            ));
+        }
+        options.seat_players()?;
+        Ok(options)
+    }
+
+    /// Checks the seats against the rest of the options. More than two
+    /// seats switch classic rules to multiplayer ones, and multiplayer rules
+    /// without seats pit the player against the AI on every other seat.
+    fn seat_players(&mut self) -> io::Result<()> {
+        if let Variant::Multiplayer { players } = self.rules {
+            let seats = self.seats.get_or_insert_with(|| {
+                let mut seats = vec![Seat::Ai; usize::from(players)];
+                seats[0] = Seat::Human;
+                seats
+            });
+            if seats.len() != usize::from(players) {
+                return Err(invalid_input(&format!(
+                    "--players needs {} players under {} rules",
+                    players, self.rules
+                )));
+            }
+        }
+        let seats = match &self.seats {
+            Some(seats) => seats.clone(),
+            None => return Ok(()),
+        };
+        if matches!(self.mode, Mode::Ultimate | Mode::Quantum) {
+            return Err(invalid_input(
+                "--players only works in classic and qubic modes",
+            ));
+        }
+        if self.swap.is_some() && seats != [Seat::Human, Seat::Ai] {
+            return Err(invalid_input(
+                "--swap only works when you play crosses against the AI",
+            ));
+        }
+        if seats.len() > 2 {
+            match self.rules {
+                Variant::Classic => {
+                    self.rules = Variant::Multiplayer {
+                        players: seats.len() as u16,
+                    }
+                }
+                Variant::Multiplayer { .. } => {}
+                _ => {
+                    return Err(invalid_input(&format!(
+                        "{} rules are for two players",
+                        self.rules
+                    )))
+                }
+            }
+            if self.ai == AI::MiniMax && seats.contains(&Seat::Ai) {
+                return Err(invalid_input(
+                    "MiniMax cannot play games of more than two players",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an m,n,k board configuration written as `MxNxK`. The win length may
+/// be left out, in which case it is the shorter side of the board.
+pub fn parse_board_config(config: &str) -> io::Result<(u16, u16, u16)> {
+    let numbers = config
+        .split('x')
+        .map(|number| number.parse::<u16>())
+        .collect::<Result<Vec<u16>, _>>()
+        .map_err(|_| invalid_input(&format!("invalid board: {}", config)))?;
+    let (width, height, win_length) = match numbers[..] {
+        [width, height] => (width, height, width.min(height)),
+        [width, height, win_length] => (width, height, win_length),
+        _ => return Err(invalid_input(&format!("invalid board: {}", config))),
+    };
+    if width == 0 || height == 0 || win_length == 0 || win_length > width.max(height) {
+        return Err(invalid_input(&format!("invalid board: {}", config)));
+    }
+    Ok((width, height, win_length))
+}
+
+/// Parses the seats of a game given as a comma-separated list such as
+/// `human,ai,ai`, one for each player in turn order.
+pub fn parse_seats(seats: &str) -> io::Result<Vec<Seat>> {
+    let seats = seats
+        .split(',')
+        .map(str::parse)
+        .collect::<io::Result<Vec<Seat>>>()?;
+    if seats.len() < 2 || seats.len() > Player::ALL.len() {
+        return Err(invalid_input(&format!(
+            "--players needs between 2 and {} players",
+            Player::ALL.len()
+        )));
+    }
+    Ok(seats)
+}
+
+/// Parses the value which follows a flag such as `--seed`.
+fn flag_value<T: FromStr>(flag: &str, value: Option<String>) -> io::Result<T> {
+    let value = value.ok_or_else(|| invalid_input(&format!("{} needs a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| invalid_input(&format!("invalid value for {}: {}", flag, value)))
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
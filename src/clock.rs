@@ -0,0 +1,118 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Chess-style time control: every player starts with `base` time on their
+/// clock and gets `increment` added back after each move they make.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl FromStr for TimeControl {
+    type Err = io::Error;
+
+    /// Parses a time control written as `BASE+INCREMENT` in seconds, such as
+    /// `300+5`. The increment may be left out.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid time control: {}", s),
+            )
+        };
+        let mut parts = s.splitn(2, '+');
+        let base: u64 = parts
+            .next()
+            .and_then(|base| base.parse().ok())
+            .ok_or_else(invalid)?;
+        let increment: u64 = match parts.next() {
+            Some(increment) => increment.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        if base == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            base: Duration::from_secs(base),
+            increment: Duration::from_secs(increment),
+        })
+    }
+}
+
+/// A player's game clock. It only runs between `start` and `stop`, and a
+/// player whose clock reaches zero has lost on time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clock {
+    remaining: Duration,
+    increment: Duration,
+    started_at: Option<Instant>,
+}
+
+impl Clock {
+    pub fn from(time_control: TimeControl) -> Self {
+        Self {
+            remaining: time_control.base,
+            increment: time_control.increment,
+            started_at: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    pub fn start(&mut self, now: Instant) {
+        if self.started_at.is_none() {
+            self.started_at = Some(now);
+        }
+    }
+
+    /// Stops the clock once the player has moved, adding the increment unless
+    /// the player had already run out of time.
+    pub fn stop(&mut self, now: Instant) {
+        if self.started_at.is_some() {
+            self.remaining = self.remaining(now);
+            self.started_at = None;
+            if !self.remaining.is_zero() {
+                self.remaining += self.increment;
+            }
+        }
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        match self.started_at {
+            Some(started_at) => self
+                .remaining
+                .saturating_sub(now.saturating_duration_since(started_at)),
+            None => self.remaining,
+        }
+    }
+
+    pub fn is_flagged(&self, now: Instant) -> bool {
+        self.remaining(now).is_zero()
+    }
+
+    /// The time left on the clock formatted as `m:ss`, with tenths of a second
+    /// once fewer than ten seconds remain.
+    pub fn display(&self, now: Instant) -> String {
+        format_duration(self.remaining(now))
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds < 10 {
+        format!("0:0{}.{}", seconds, duration.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}+{}", self.base.as_secs(), self.increment.as_secs())
+    }
+}
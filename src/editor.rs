@@ -0,0 +1,202 @@
+use super::board::Board;
+use super::game::{key_direction, print_message};
+use super::grid::Grid;
+use super::rules::Rules;
+use super::{Coordinates, Player};
+
+use crossterm::event::{read, Event, KeyCode};
+use crossterm::{cursor, execute};
+use std::io;
+use std::io::stdout;
+
+/// What to do with the position once it has been set up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorChoice {
+    Play,
+    Analyze,
+    Quit,
+}
+
+/// Sets up a position by putting marks on the board, or taking them off, in
+/// any order, and picking the player to move.
+pub struct Editor {
+    pub board: Board,
+    pub grid: Grid,
+    pub to_move: Player,
+    cursor: Coordinates,
+    message: Option<String>,
+}
+
+/// Checks that the position could come up in a game between crosses and
+/// zeros where crosses move first: the players have made as many moves as
+/// the player to move says, neither has more marks than the rules allow, as
+/// under sliding rules, under gravity no mark floats above an empty box,
+/// and nobody has completed a line yet. Rules which let players place
+/// either symbol leave the number of marks open.
+pub fn validate(board: &Board, to_move: Player, rules: &dyn Rules) -> io::Result<()> {
+    let crosses = board.marks_of(Player::Cross).len();
+    let zeros = board.marks_of(Player::Zero).len();
+    if crosses + zeros != board.marked_positions().len() {
+        return Err(invalid_position("only crosses and zeros can be set up"));
+    }
+    if !rules.allows_any_symbol() {
+        match to_move {
+            Player::Cross if crosses != zeros => {
+                return Err(invalid_position(
+                    "crosses move next, so there must be as many crosses as zeros",
+                ))
+            }
+            Player::Zero if crosses != zeros + 1 => {
+                return Err(invalid_position(
+                    "zeros move next, so there must be one cross more than zeros",
+                ))
+            }
+            _ => {}
+        }
+    }
+    if let Some(max_marks) = rules.max_marks() {
+        if crosses.max(zeros) > max_marks {
+            return Err(invalid_position(&format!(
+                "players can have no more than {} marks on the board",
+                max_marks
+            )));
+        }
+    }
+    if board.gravity {
+        let floating = board.marked_positions().keys().any(|position| {
+            let below = Coordinates {
+                y: position.y + 1,
+                ..*position
+            };
+            board.contains(&below) && !board.is_blocked(&below) && board.get(&below).is_none()
+        });
+        if floating {
+            return Err(invalid_position("a mark floats above an empty box"));
+        }
+    }
+    match (
+        board.has_line(&Player::Cross),
+        board.has_line(&Player::Zero),
+    ) {
+        (true, true) => Err(invalid_position("both players have a line")),
+        (true, false) => Err(invalid_position("crosses already have a line")),
+        (false, true) => Err(invalid_position("zeros already have a line")),
+        (false, false) => Ok(()),
+    }
+}
+
+impl Editor {
+    /// Edits the position on the given board, which may have marks on it
+    /// already.
+    pub fn new(board: Board) -> Self {
+        let grid = Grid {
+            width: board.width,
+            height: board.height,
+            layers: board.depth,
+        };
+        let cursor = board
+            .positions()
+            .first()
+            .copied()
+            .unwrap_or(Coordinates { x: 0, y: 0, z: 0 });
+        Self {
+            board,
+            grid,
+            to_move: Player::Cross,
+            cursor,
+            message: None,
+        }
+    }
+
+    /// Puts the player's mark on the position, replacing any mark there, or
+    /// clears the position if there is no player.
+    pub fn set(&mut self, position: Coordinates, player: Option<Player>) -> io::Result<()> {
+        if self.board.is_blocked(&position) {
+            return Err(invalid_position("the position is blocked"));
+        }
+        let old = self.board.unmark(&position);
+        if let Some(player) = player {
+            if let Err(e) = self.board.mark(position, player) {
+                if let Some(old) = old {
+                    self.board.mark(position, old)?;
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows the editor until the position is played, analyzed or left.
+    /// The cursor is moved around the grid as in a game; `x` and `o` put a
+    /// mark on the box under it and Space clears it, Tab switches the player
+    /// to move, and the position is only played or analyzed once it passes
+    /// `validate`.
+    pub fn show(&mut self, rules: &dyn Rules) -> crossterm::Result<EditorChoice> {
+        loop {
+            self.draw()?;
+            self.message = None;
+            let code = match read()? {
+                Event::Key(k) => k.code,
+                _ => continue,
+            };
+            let choice = match code {
+                KeyCode::Char('p') => EditorChoice::Play,
+                KeyCode::Char('e') => EditorChoice::Analyze,
+                KeyCode::Esc => return Ok(EditorChoice::Quit),
+                code => {
+                    if let Err(e) = self.handle_key(code) {
+                        self.message = Some(e.to_string());
+                    }
+                    continue;
+                }
+            };
+            match validate(&self.board, self.to_move, rules) {
+                Ok(()) => return Ok(choice),
+                Err(e) => self.message = Some(e.to_string()),
+            }
+        }
+    }
+
+    /// Edits the position, or moves the cursor, as the key says.
+    fn handle_key(&mut self, code: KeyCode) -> io::Result<()> {
+        if let Some(direction) = key_direction(code) {
+            self.cursor = self.board.cursor_step(self.cursor, &direction);
+            return Ok(());
+        }
+        match code {
+            KeyCode::Char('x') => return self.set(self.cursor, Some(Player::Cross)),
+            KeyCode::Char('o') | KeyCode::Char('0') => {
+                return self.set(self.cursor, Some(Player::Zero))
+            }
+            KeyCode::Char(' ') | KeyCode::Backspace | KeyCode::Delete => {
+                return self.set(self.cursor, None)
+            }
+            KeyCode::Tab => self.to_move = self.to_move.opponent(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Redraws the grid with the position, and the player to move below it.
+    fn draw(&mut self) -> crossterm::Result<()> {
+        self.grid.draw()?.draw_position(&self.board)?;
+        let row = self.grid.bottom() + 1;
+        print_message(
+            row,
+            &format!("Setting up, {} to move", self.to_move.to_char()),
+        )?;
+        let help = "wasd: cursor  x/o: mark  Space: clear  Tab: side to move  \
+                    p: play  e: analyze  Esc: quit";
+        print_message(row + 1, self.message.as_deref().unwrap_or(help))?;
+        let screen_coords = self.grid.screen_coords(&self.cursor);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+        )?;
+        Ok(())
+    }
+}
+
+fn invalid_position(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
@@ -1,43 +1,218 @@
-use super::ai::AI;
+use super::ai::{Evaluation, AI, DEFAULT_THINK_TIME};
+use super::board::Board;
+use super::clock::{Clock, TimeControl};
 use super::grid::Grid;
-use super::{Coordinates, Direction, InputEvent, Player, Side};
+use super::prompt::{self, GameCommand};
+use super::record::GameRecord;
+use super::rules::{pick_move, Pick, Rules, Variant};
+use super::series::read_line_completing;
+use super::swap::Swap;
+use super::{Coordinates, Direction, InputEvent, Move, Outcome, Player};
 
-use crossterm::event::{read, Event};
+use crossterm::event::{poll, read, Event};
 use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
 use crossterm::{cursor, event, execute};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use std::collections::HashMap;
+use std::fs;
 use std::io;
 use std::io::stdout;
-use std::iter;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, PartialEq)]
+/// How often the clocks are redrawn while waiting for the player to move.
+const CLOCK_REFRESH: Duration = Duration::from_millis(100);
+
+/// How often a position has to come up for the game to be drawn.
+const REPETITIONS_FOR_DRAW: u32 = 3;
+
+/// Number of screen columns the status next to the clocks takes up.
+const STATUS_WIDTH: usize = 30;
+
+/// Who makes the moves of a player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Seat {
+    /// A person at the keyboard.
+    Human,
+    /// The game's AI.
+    Ai,
+}
+
+impl FromStr for Seat {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "human" => Ok(Self::Human),
+            "ai" => Ok(Self::Ai),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown seat: {}", s),
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct TicTacToe {
     pub cursor: Coordinates,
     pub grid: Grid,
     pub ai_algo: AI,
-    pub marked_positions: HashMap<Coordinates, Player>,
+    pub board: Board,
+    /// The rules the game is played by; use `set_rules` to change.
+    pub rules: Box<dyn Rules>,
+    /// Every move played so far, along with the seed of the AI's random number
+    /// generator.
+    pub record: GameRecord,
+    /// Game clocks of the players, left empty for untimed games.
+    pub clocks: HashMap<Player, Clock>,
+    /// The most time the AI may think about a single move.
+    pub ai_time: Duration,
+    /// Who moves for each player, in the order of `Player::ALL`: by default
+    /// the player plays crosses against the AI. Use `set_seats` to change.
+    pub seats: Vec<Seat>,
+    /// The symbol the player places next, which only changes under rules that
+    /// allow either symbol.
+    pub symbol: Player,
+    /// The piece the player has picked up to slide elsewhere.
+    pub selected: Option<Coordinates>,
+    /// The swap rule the game opens with, if any; use `set_swap` to change.
+    pub swap: Option<Swap>,
+    /// Who places the opening stones under a swap rule, leaving the choice of
+    /// sides to the other.
+    pub opener: Player,
+    /// How the game ended, once it has. Stays `None` if the game is quit, or
+    /// if a player of a game of more than two runs out of time.
+    pub outcome: Option<Outcome>,
+    /// A command typed at the prompt which ended the game without an
+    /// outcome and is left to whoever runs the game, such as starting a new
+    /// one.
+    pub requested: Option<GameCommand>,
+    /// The player whose turn it is.
+    to_move: Player,
+    /// How often each position has come up, along with whose turn it was;
+    /// see `repetition_key`.
+    repetitions: HashMap<(u64, Player, Vec<Coordinates>), u32>,
+    rng: StdRng,
 }
 
 impl TicTacToe {
     pub fn from(grid: Grid, ai_algo: AI) -> crossterm::Result<Self> {
-        let initial_grid_coords = Coordinates { x: 0, y: 0 };
+        Self::from_seed(grid, ai_algo, rand::random())
+    }
+
+    /// Sets up a game whose AI draws its random numbers from the given seed,
+    /// so that the same moves from the player get the same replies.
+    pub fn from_seed(grid: Grid, ai_algo: AI, seed: u64) -> crossterm::Result<Self> {
+        let initial_grid_coords = Coordinates { x: 0, y: 0, z: 0 };
         Self::move_cursor_to_grid(&initial_grid_coords)?;
-        let Side(side) = grid.side;
-        let marked_positions: HashMap<Coordinates, Player> =
-            HashMap::with_capacity(side.pow(2).into());
+        let board = Board::layered(
+            grid.width,
+            grid.height,
+            grid.layers,
+            grid.width.min(grid.height),
+        );
+        let record = GameRecord::from(&board, seed);
         Ok(Self {
             cursor: initial_grid_coords,
-            grid: grid,
-            ai_algo: ai_algo,
-            marked_positions: marked_positions,
+            grid,
+            ai_algo,
+            board,
+            rules: Box::new(Variant::default()),
+            record,
+            clocks: HashMap::new(),
+            ai_time: DEFAULT_THINK_TIME,
+            seats: vec![Seat::Human, Seat::Ai],
+            symbol: Player::Cross,
+            selected: None,
+            swap: None,
+            opener: Player::Cross,
+            outcome: None,
+            requested: None,
+            to_move: Player::Cross,
+            repetitions: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
         })
     }
 
+    /// Plays on the given board instead, which has to fit the grid. Use this
+    /// to change the win length or to turn on gravity before the game starts.
+    pub fn set_board(&mut self, board: Board) {
+        let rules = self.record.rules.clone();
+        let swap = self.record.swap;
+        self.record = GameRecord::from(&board, self.record.seed);
+        self.record.rules = rules;
+        self.record.swap = swap;
+        self.board = board;
+    }
+
+    /// Starts the game from a position set up on the board, with the given
+    /// player to move first.
+    pub fn set_position(&mut self, board: Board, to_move: Player) {
+        let record = GameRecord::from_position(&board, to_move, self.record.seed);
+        self.set_board(board);
+        self.record.setup = record.setup;
+        self.record.first = to_move;
+        self.to_move = to_move;
+    }
+
+    /// Plays by the given rules instead, which may be any of the built-in
+    /// variants or rules of your own.
+    pub fn set_rules<R: Rules + 'static>(&mut self, rules: R) {
+        self.record.rules = rules.to_string();
+        self.rules = Box::new(rules);
+    }
+
+    /// Opens the game with the given swap rule, where `opener` places the
+    /// opening stones and the other player then picks a side.
+    pub fn set_swap(&mut self, swap: Swap, opener: Player) {
+        self.record.swap = Some(swap);
+        self.swap = Some(swap);
+        self.opener = opener;
+    }
+
+    /// Seats the players, one seat for each player in the order of
+    /// `Player::ALL`. Games of more than two players also need rules which
+    /// let them all take turns, such as `Variant::Multiplayer`.
+    pub fn set_seats(&mut self, seats: Vec<Seat>) {
+        self.seats = seats;
+    }
+
+    /// Every player of the game, in the order they take turns.
+    pub fn players(&self) -> &[Player] {
+        &Player::ALL[..self.seats.len()]
+    }
+
+    /// Gives every player a game clock with the given time control.
+    pub fn set_time_control(&mut self, time_control: TimeControl) {
+        self.clocks = Player::ALL[..self.seats.len()]
+            .iter()
+            .map(|player| (*player, Clock::from(time_control)))
+            .collect();
+    }
+
     /// The game loop reads player input and performs actions based on this input.
     pub fn game_loop(&mut self) -> crossterm::Result<()> {
         let mut event: InputEvent;
+        self.draw_board()?;
+        if self.swap.is_some() && self.opener == Player::Zero && self.ai_opening()? {
+            return Ok(());
+        }
+        // The AI may be seated to move first.
+        if self.ai_turns()? {
+            return Ok(());
+        }
+        self.draw_status()?;
         loop {
             event = self.read_input_event()?;
+            if event == InputEvent::Prompt {
+                event = match self.command_prompt()? {
+                    Some(event) => event,
+                    None => continue,
+                };
+            }
             match event {
                 InputEvent::Direction(direction) => {
                     self.handle_direction(direction)?;
@@ -45,116 +220,655 @@ impl TicTacToe {
                 InputEvent::Quit => {
                     break;
                 }
+                InputEvent::ToggleSymbol => {
+                    if self.rules.allows_any_symbol() {
+                        self.symbol = self.symbol.opponent();
+                        self.draw_status()?;
+                    }
+                }
+                InputEvent::Prompt => {}
+                InputEvent::Tick => {
+                    self.draw_clocks()?;
+                    if self.is_flagged(&self.to_move) {
+                        self.announce_flag(self.to_move)?;
+                        break;
+                    }
+                }
                 InputEvent::Mark => {
-                    let marked = self.mark_cross();
+                    let player_move = match self.player_move()? {
+                        Some(player_move) => player_move,
+                        None => continue,
+                    };
+                    let marked = self.play(player_move);
                     // Let's ignore if the player sets a mark at an already marked position.
                     if marked.is_err() {
                         continue;
                     }
-                    let player_has_won = self.check_for_victory(&Player::Cross);
-                    if player_has_won {
-                        self.screen_message("You've won the game!")?;
+                    let player = self.to_move;
+                    let player_position = self.last_position();
+                    self.stop_clock(player)?;
+                    if self.is_flagged(&player) {
+                        self.announce_flag(player)?;
                         break;
                     }
-                    let game_has_drawed = !self.grid_has_empty_boxes();
-                    if game_has_drawed {
-                        self.screen_message("The game was a draw!")?;
+                    // Opening stones count for the side whose symbol they bear.
+                    let side = if self.swap.is_some() && self.opener == player {
+                        player_move.symbol
+                    } else {
+                        player
+                    };
+                    if let Some(outcome) = self.outcome_after(&player_position, side) {
+                        self.announce(outcome)?;
                         break;
                     }
-                    let player_cursor = self.cursor;
-                    let ai_cursor = self
-                        .ai_algo
-                        .get_marker(&self.marked_positions, &self.grid.side);
-                    self.set_cursor_to_grid(&ai_cursor)?;
-                    self.mark_zero()?;
-                    let ai_has_won = self.check_for_victory(&Player::Zero);
-                    if ai_has_won {
-                        self.screen_message("AI won the game!")?;
+                    if self.opening_symbol().is_some() {
+                        self.start_clock(player)?;
+                        self.draw_status()?;
+                        continue;
+                    }
+                    if self.opening_just_ended() && self.ai_swaps()? {
+                        self.start_clock(player)?;
+                        continue;
+                    }
+                    self.to_move = self.rules.next_player(&self.board, player);
+                    if self.ai_turns()? {
                         break;
                     }
-                    self.set_cursor_to_grid(&player_cursor)?;
+                    self.draw_status()?;
                 }
             }
         }
         Ok(())
     }
 
-    pub fn screen_message(&self, msg: &str) -> crossterm::Result<()> {
-        let Side(side) = self.grid.side;
-        // Cleanup any previous text
+    /// Lets the AI move for as long as the rules give the turn to players
+    /// seated with it, and then hands the turn to the next human player.
+    /// Returns whether the game is over.
+    fn ai_turns(&mut self) -> crossterm::Result<bool> {
+        let player_cursor = self.cursor;
+        while self.seat(self.to_move) == Seat::Ai {
+            let player = self.to_move;
+            self.start_clock(player)?;
+            let deadline = self.ai_deadline(player);
+            let ai_move = self.ai_algo.get_move_within(
+                &self.board,
+                self.rules.as_ref(),
+                player,
+                &mut self.rng,
+                deadline,
+            );
+            self.stop_clock(player)?;
+            if self.is_flagged(&player) {
+                self.announce_flag(player)?;
+                return Ok(true);
+            }
+            self.set_cursor_to_grid(&ai_move.position)?;
+            if let Some(outcome) = self.play_move(ai_move)? {
+                self.announce(outcome)?;
+                return Ok(true);
+            }
+        }
+        self.set_cursor_to_grid(&player_cursor)?;
+        self.start_clock(self.to_move)?;
+        Ok(false)
+    }
+
+    /// Who moves for the player.
+    fn seat(&self, player: Player) -> Seat {
+        self.seats.get(player.index()).copied().unwrap_or(Seat::Ai)
+    }
+
+    /// How the player is called in messages: "You" for the only person at
+    /// the keyboard and "AI" when the AI plays a single player, and by their
+    /// symbol otherwise.
+    fn name(&self, player: Player) -> String {
+        let seat = self.seat(player);
+        let count = self.seats.iter().filter(|other| **other == seat).count();
+        match seat {
+            Seat::Human if count == 1 => "You".to_string(),
+            Seat::Ai if count == 1 => "AI".to_string(),
+            Seat::Human => format!("Player {}", player.to_char()),
+            Seat::Ai => format!("AI {}", player.to_char()),
+        }
+    }
+
+    /// The symbol of the opening stone the opener places next under a swap
+    /// rule, or `None` once the opening is over.
+    fn opening_symbol(&self) -> Option<Player> {
+        let opening = self.swap?.opening(self.opener);
+        opening.get(self.record.moves.len()).copied()
+    }
+
+    /// Whether the latest move placed the last opening stone.
+    fn opening_just_ended(&self) -> bool {
+        match self.swap {
+            Some(swap) => swap.opening(self.opener).len() == self.record.moves.len(),
+            None => false,
+        }
+    }
+
+    /// Lets the AI decide whether to take over the player's opening stones.
+    /// Returns whether it did, in which case it is the player's turn again.
+    fn ai_swaps(&mut self) -> crossterm::Result<bool> {
+        let deadline = self.ai_deadline(Player::Zero);
+        self.draw_status()?;
+        let swaps = self.ai_algo.wants_swap(
+            &self.board,
+            self.rules.as_ref(),
+            Player::Zero,
+            &mut self.rng,
+            deadline,
+        );
+        if swaps {
+            self.swap_sides()?;
+            self.screen_message("AI swapped sides and took your opening stones")?;
+        } else {
+            self.screen_message("AI kept its side")?;
+        }
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))?;
+        Ok(swaps)
+    }
+
+    /// Has the AI place the opening stones, at random so that neither side
+    /// is obviously better off, and asks the player whether to take them
+    /// over. Returns whether the game is over.
+    fn ai_opening(&mut self) -> crossterm::Result<bool> {
+        let player_cursor = self.cursor;
+        while let Some(symbol) = self.opening_symbol() {
+            let legal_moves: Vec<Move> = self
+                .rules
+                .legal_moves(&self.board, symbol)
+                .into_iter()
+                .filter(|next| next.symbol == symbol)
+                .collect();
+            let next = *legal_moves
+                .choose(&mut self.rng)
+                .expect("the board has room for the opening");
+            self.set_cursor_to_grid(&next.position)?;
+            self.play(next)?;
+            let position = self.last_position();
+            if let Some(outcome) = self.outcome_after(&position, symbol) {
+                self.announce(outcome)?;
+                return Ok(true);
+            }
+        }
+        self.screen_message("Swap sides and take the AI's opening stones? (y/n)")?;
+        let swaps = read_yes_no()?;
+        self.screen_message("")?;
+        self.set_cursor_to_grid(&player_cursor)?;
+        if swaps {
+            self.swap_sides()?;
+            self.to_move = Player::Zero;
+            return self.ai_turns();
+        }
+        Ok(false)
+    }
+
+    /// Turns the symbols of every mark around, as the players swap sides, and
+    /// redraws them.
+    fn swap_sides(&mut self) -> crossterm::Result<()> {
+        self.board.swap_sides();
+        self.record.swapped = true;
+        let marks: Vec<(Coordinates, Player)> = self
+            .board
+            .marked_positions()
+            .iter()
+            .map(|(position, symbol)| (*position, *symbol))
+            .collect();
+        for (position, symbol) in marks {
+            Self::move_cursor_to_screen(&self.grid.screen_coords(&position))?;
+            self.grid.mark_at(position, symbol.to_char())?;
+        }
+        self.fade_vanishing_marks()?;
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))?;
+        Ok(())
+    }
+
+    /// The move the player makes by pressing Enter at the cursor, if any.
+    /// Once the player gets to choose which of their pieces to move, as under
+    /// sliding rules, the first press picks up a piece and the second puts it
+    /// down; pressing Enter on the picked up piece again puts it back.
+    fn player_move(&mut self) -> crossterm::Result<Option<Move>> {
+        // While opening under a swap rule, the player places the stones of
+        // either side.
+        let (player, symbol) = match self.opening_symbol() {
+            Some(symbol) => (symbol, symbol),
+            None if self.rules.allows_any_symbol() => (self.to_move, self.symbol),
+            None => (self.to_move, self.to_move),
+        };
+        let pick = pick_move(
+            &self.board,
+            self.rules.as_ref(),
+            player,
+            symbol,
+            self.cursor,
+            self.selected,
+        );
+        match pick {
+            Ok(Pick::Move(next)) => {
+                if self.selected.take().is_some() {
+                    self.draw_status()?;
+                }
+                return Ok(Some(next));
+            }
+            Ok(Pick::PickUp(piece)) => {
+                self.selected = Some(piece);
+                self.grid.highlight_at(piece, player.to_char())?;
+            }
+            Ok(Pick::PutBack(piece)) => {
+                self.selected = None;
+                self.grid.mark_at(piece, player.to_char())?;
+            }
+            // Let's ignore if the player picks a box they cannot move to.
+            Err(_) => {}
+        }
+        self.draw_status()?;
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))?;
+        Ok(None)
+    }
+
+    /// Asks for a move or a command at the prompt below the grid and carries
+    /// it out, telling the player why if it cannot be. Returns what it comes
+    /// down to in the game loop, if anything: marking the box typed, or
+    /// quitting the game for whoever runs it to start a new one.
+    fn command_prompt(&mut self) -> crossterm::Result<Option<InputEvent>> {
+        let line = read_line_completing(self.grid.bottom() + 1, ":", &prompt::complete)?;
+        self.screen_message("")?;
+        let result = match line.filter(|line| !line.trim().is_empty()) {
+            Some(line) => line.parse().and_then(|command| self.run_command(command)),
+            None => Ok(None),
+        };
+        let event = match result {
+            Ok(event) => event,
+            Err(e) => {
+                self.screen_message(&e.to_string())?;
+                None
+            }
+        };
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))?;
+        Ok(event)
+    }
+
+    /// Carries out a command typed at the prompt; see `command_prompt`.
+    pub fn run_command(&mut self, command: GameCommand) -> crossterm::Result<Option<InputEvent>> {
+        match command {
+            GameCommand::Mark(position) => {
+                if !self.board.contains(&position) {
+                    return Err(invalid_command(&format!(
+                        "there is no box {} on the board",
+                        position
+                    )));
+                }
+                // Under gravity the cursor stays on the top row.
+                if self.board.gravity {
+                    self.set_cursor_to_grid(&Coordinates { y: 0, ..position })?;
+                } else {
+                    self.set_cursor_to_grid(&position)?;
+                }
+                return Ok(Some(InputEvent::Mark));
+            }
+            GameCommand::Undo => self.undo()?,
+            GameCommand::New(_) => {
+                self.requested = Some(command);
+                return Ok(Some(InputEvent::Quit));
+            }
+            GameCommand::Ai(ai) => {
+                if ai == AI::MiniMax && self.seats.len() > 2 {
+                    return Err(invalid_command(
+                        "MiniMax cannot play games of more than two players",
+                    ));
+                }
+                self.ai_algo = ai;
+                self.screen_message(&format!("The AI is now {}", ai))?;
+            }
+            GameCommand::Save(path) => {
+                fs::write(&path, self.record.to_string())?;
+                self.screen_message(&format!("Saved the game to {}", path.display()))?;
+            }
+            GameCommand::Hint => {
+                let (evaluation, hint) = self.hint()?;
+                let mut message = format!("Hint: {} at {}", hint.symbol.to_char(), hint.position);
+                if let Some(from) = hint.from {
+                    message += &format!(" from {}", from);
+                }
+                self.screen_message(&format!("{} ({})", message, evaluation))?;
+                // Put the cursor where Enter plays the hint, or starts to.
+                let mut cursor = hint.from.unwrap_or(hint.position);
+                if self.board.gravity {
+                    cursor.y = 0;
+                }
+                self.cursor = cursor;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Plays a legal move for the player to move and passes the turn on, as
+    /// the game loop does, returning how the game ended if it did.
+    pub fn play_move(&mut self, next: Move) -> crossterm::Result<Option<Outcome>> {
+        let player = self.to_move;
+        self.play(next)?;
+        let position = self.last_position();
+        if let Some(outcome) = self.outcome_after(&position, player) {
+            return Ok(Some(outcome));
+        }
+        self.to_move = self.rules.next_player(&self.board, player);
+        Ok(None)
+    }
+
+    /// The move the engine suggests to the player to move, along with how it
+    /// judges the position. Only games of two players get hints.
+    pub fn hint(&self) -> io::Result<(Evaluation, Move)> {
+        if self.seats.len() > 2 {
+            return Err(invalid_command("hints only work in games of two players"));
+        }
+        let deadline = Instant::now() + self.ai_time;
+        match AI::evaluate(&self.board, self.rules.as_ref(), self.to_move, deadline) {
+            (evaluation, Some(hint)) => Ok((evaluation, hint)),
+            (_, None) => Err(invalid_command("there is no move to suggest")),
+        }
+    }
+
+    /// Takes back the latest move of a person at the keyboard, along with
+    /// the moves the AI made after it, so that it is their turn again.
+    pub fn undo(&mut self) -> crossterm::Result<()> {
+        if self.swap.is_some() {
+            return Err(invalid_command(
+                "moves cannot be taken back in games opened with a swap rule",
+            ));
+        }
+        // Play the game through again to find out who made each move.
+        let mut board = self.record.board();
+        let mut player = self.record.first;
+        let mut movers = Vec::with_capacity(self.record.moves.len());
+        for next in &self.record.moves {
+            movers.push(player);
+            self.rules.apply(&mut board, next)?;
+            player = self.rules.next_player(&board, player);
+        }
+        let last = movers
+            .iter()
+            .rposition(|player| self.seat(*player) == Seat::Human)
+            .ok_or_else(|| invalid_command("there is no move of yours to take back"))?;
+        self.record.moves.truncate(last);
+        self.board = self.record.board();
+        self.repetitions.clear();
+        for (next, player) in self.record.moves.iter().zip(&movers) {
+            self.rules.apply(&mut self.board, next)?;
+            let key = self.repetition_key(*player);
+            *self.repetitions.entry(key).or_insert(0) += 1;
+        }
+        self.to_move = movers[last];
+        self.selected = None;
+        self.draw_marks()?;
+        self.draw_status()
+    }
+
+    /// Redraws every box of the board with the mark on it, if any, such as
+    /// after moves have been taken back.
+    fn draw_marks(&mut self) -> crossterm::Result<()> {
+        for position in self.board.positions() {
+            let marker = self.board.get(&position).map_or(' ', Player::to_char);
+            Self::move_cursor_to_screen(&self.grid.screen_coords(&position))?;
+            self.grid.mark_at(position, marker)?;
+        }
+        self.fade_vanishing_marks()?;
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))
+    }
+
+    /// Draws the blocked boxes of the board and any marks set up on it before
+    /// the game, and moves the cursor off them.
+    fn draw_board(&mut self) -> crossterm::Result<()> {
+        for position in self.board.blocked_positions() {
+            self.grid.block_at(position)?;
+        }
+        for next in &self.record.setup {
+            Self::move_cursor_to_screen(&self.grid.screen_coords(&next.position))?;
+            self.grid.mark_at(next.position, next.symbol.to_char())?;
+        }
+        let mut cursor = self.cursor;
+        if self.board.is_blocked(&cursor) {
+            cursor = self.board.positions().first().copied().unwrap_or(cursor);
+        }
+        self.set_cursor_to_grid(&cursor)
+    }
+
+    /// The position marked by the latest move.
+    fn last_position(&self) -> Coordinates {
+        self.record
+            .moves
+            .last()
+            .expect("a move has been played")
+            .position
+    }
+
+    /// How the game ended after the player marked the given position, or
+    /// `None` if it goes on. On top of the rules, the game is drawn once the
+    /// same position comes up for the third time.
+    fn outcome_after(&mut self, position: &Coordinates, player: Player) -> Option<Outcome> {
+        if let Some(outcome) = self.rules.outcome(&self.board, position, player) {
+            return Some(outcome);
+        }
+        let key = self.repetition_key(player);
+        let count = self.repetitions.entry(key).or_insert(0);
+        *count += 1;
+        if *count >= REPETITIONS_FOR_DRAW {
+            Some(Outcome::Draw)
+        } else {
+            None
+        }
+    }
+
+    /// What makes the position after the player's move the same as another
+    /// one: the marks on the board and whose turn it is. Once marks vanish,
+    /// the order each player's marks were placed in counts too, as it decides
+    /// which of them vanish next.
+    fn repetition_key(&self, player: Player) -> (u64, Player, Vec<Coordinates>) {
+        let players = self.players();
+        let vanishing = players
+            .iter()
+            .any(|player| self.rules.vanishing_mark(&self.board, *player).is_some());
+        let ages = if vanishing {
+            players
+                .iter()
+                .flat_map(|player| self.board.marks_of(*player))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        (
+            self.board.zobrist_hash(),
+            self.rules.next_player(&self.board, player),
+            ages,
+        )
+    }
+
+    /// Tells the players how the game ended.
+    fn announce(&mut self, outcome: Outcome) -> crossterm::Result<()> {
+        self.outcome = Some(outcome);
+        match outcome {
+            Outcome::Win(player) if self.name(player) == "You" => {
+                self.screen_message("You've won the game!")
+            }
+            Outcome::Win(player) => {
+                self.screen_message(&format!("{} won the game!", self.name(player)))
+            }
+            Outcome::Draw => self.screen_message("The game was a draw!"),
+        }
+    }
+
+    /// Tells the players who ran out of time. In a two-player game that
+    /// loses it.
+    fn announce_flag(&mut self, player: Player) -> crossterm::Result<()> {
+        if self.seats.len() == 2 {
+            self.outcome = Some(Outcome::Win(player.opponent()));
+        }
+        self.screen_message(&format!("{} ran out of time!", self.name(player)))
+    }
+
+    /// The moment the AI has to decide on the player's move by. With clocks
+    /// running the AI also spends no more than a tenth of the player's
+    /// remaining time on a move.
+    fn ai_deadline(&self, player: Player) -> Instant {
+        let now = Instant::now();
+        let budget = match self.clocks.get(&player) {
+            Some(clock) => self.ai_time.min(clock.remaining(now) / 10),
+            None => self.ai_time,
+        };
+        now + budget
+    }
+
+    fn start_clock(&mut self, player: Player) -> crossterm::Result<()> {
+        if let Some(clock) = self.clocks.get_mut(&player) {
+            clock.start(Instant::now());
+        }
+        self.draw_clocks()
+    }
+
+    fn stop_clock(&mut self, player: Player) -> crossterm::Result<()> {
+        if let Some(clock) = self.clocks.get_mut(&player) {
+            clock.stop(Instant::now());
+        }
+        self.draw_clocks()
+    }
+
+    fn is_flagged(&self, player: &Player) -> bool {
+        self.clocks
+            .get(player)
+            .is_some_and(|clock| clock.is_flagged(Instant::now()))
+    }
+
+    /// Shows the time left on each player's clock right below the grid.
+    fn draw_clocks(&self) -> crossterm::Result<()> {
+        if self.clocks.is_empty() {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let clocks: Vec<String> = self
+            .players()
+            .iter()
+            .filter_map(|player| {
+                let clock = self.clocks.get(player)?;
+                Some(format!("{} {}", player.to_char(), clock.display(now)))
+            })
+            .collect();
         execute!(
             stdout(),
-            cursor::MoveTo(0, side + 1),
-            SetBackgroundColor(Color::Black),
-            Print(iter::repeat(" ").take(100).collect::<String>()),
-            ResetColor
+            cursor::SavePosition,
+            cursor::MoveTo(0, self.grid.bottom()),
+            Print(format!("{:<30}", clocks.join("  "))),
+            cursor::RestorePosition
         )?;
+        Ok(())
+    }
+
+    /// Tells the player what their next press of Enter does, next to the
+    /// clocks: which symbol it places under rules which let the player pick,
+    /// where the picked up piece goes under sliding rules, or whose turn it is
+    /// when several people play. Otherwise it lists the latest moves.
+    fn draw_status(&self) -> crossterm::Result<()> {
+        let status = if let Some(symbol) = self.opening_symbol() {
+            let opening = self.swap.map_or(0, |swap| swap.opening(self.opener).len());
+            format!(
+                "Opening stone {} of {}: {}",
+                self.record.moves.len() + 1,
+                opening,
+                symbol.to_char()
+            )
+        } else if self.selected.is_some() {
+            "Pick an empty box to move to".to_string()
+        } else if self.rules.allows_any_symbol() {
+            format!("Placing {} (Tab to switch)", self.symbol.to_char())
+        } else if self.name(self.to_move) != "You" {
+            // With several people at the keyboard, tell them whose turn it is.
+            format!("{} to move", self.name(self.to_move))
+        } else {
+            self.move_list(STATUS_WIDTH)
+        };
         execute!(
             stdout(),
-            cursor::MoveTo(0, side + 1),
-            SetForegroundColor(Color::Black),
-            SetBackgroundColor(Color::White),
-            Print(msg),
-            ResetColor
+            cursor::SavePosition,
+            cursor::MoveTo(30, self.grid.bottom()),
+            Print(format!("{:<width$}", status, width = STATUS_WIDTH)),
+            cursor::RestorePosition
         )?;
-        // Good idea to move the cursor on to the next line since it seems
-        // terminals in raw mode do not put an empty line at the end of STDOUT
-        // by themselves.
-        execute!(stdout(), cursor::MoveTo(0, side + 2))?;
         Ok(())
     }
 
-    /// Performs movement in the grid.
-    fn handle_direction(&mut self, direction: Direction) -> crossterm::Result<()> {
-        let Side(side) = &self.grid.side;
-        let mut grid_coords = self.cursor + direction.get_relative_coords();
-        if grid_coords.x >= *side as i16 {
-            grid_coords.x = *side as i16 - 1;
-        }
-        if grid_coords.y >= *side as i16 {
-            grid_coords.y = *side as i16 - 1;
+    /// The moves played so far in algebraic notation, such as
+    /// `Moves: b2 a1 c3`, leaving out the earliest ones which do not fit in
+    /// the given width, as in `Moves: .. a1 c3`.
+    pub fn move_list(&self, width: usize) -> String {
+        const PREFIX: &str = "Moves:";
+        const ELLIPSIS: &str = " ..";
+        if self.record.moves.is_empty() {
+            return String::new();
         }
-        if grid_coords.x < 0 {
-            grid_coords.x = 0;
+        let entries: Vec<String> = self
+            .record
+            .moves
+            .iter()
+            .map(|next| format!(" {}", next.position))
+            .collect();
+        let mut length = PREFIX.len() + entries.iter().map(String::len).sum::<usize>();
+        let mut first = 0;
+        while length > width && first < entries.len() {
+            length -= entries[first].len();
+            first += 1;
+            if first == 1 {
+                length += ELLIPSIS.len();
+            }
         }
-        if grid_coords.y < 0 {
+        let ellipsis = if first > 0 { ELLIPSIS } else { "" };
+        format!("{}{}{}", PREFIX, ellipsis, entries[first..].concat())
+    }
+
+    pub fn screen_message(&self, msg: &str) -> crossterm::Result<()> {
+        print_message(self.grid.bottom() + 1, msg)
+    }
+
+    /// Shows a line of text below the game's messages, such as the score of
+    /// a match.
+    pub fn footer(&self, text: &str) -> crossterm::Result<()> {
+        print_message(self.grid.bottom() + 2, text)
+    }
+
+    /// Performs movement in the grid. Under gravity the cursor stays on the top
+    /// row and only picks the column to drop a mark into. Blocked boxes are
+    /// skipped over, and the cursor stays put if there are only blocked boxes
+    /// ahead.
+    fn handle_direction(&mut self, direction: Direction) -> crossterm::Result<()> {
+        let mut grid_coords = self.board.cursor_step(self.cursor, &direction);
+        // Under gravity marks drop from the top row, where the cursor stays.
+        if self.board.gravity {
             grid_coords.y = 0;
         }
-        Self::move_cursor_to_grid(&grid_coords)?;
-        self.cursor = grid_coords;
-        Ok(())
+        self.set_cursor_to_grid(&grid_coords)
     }
 
     /// Read and translate keyboard input to an `InputEvent`.
     fn read_input_event(&self) -> crossterm::Result<InputEvent> {
-        loop {
-            if let Event::Key(k) = read()? {
-                match k.code {
-                    event::KeyCode::Enter => return Ok(InputEvent::Mark),
-                    event::KeyCode::Char('w') => return Ok(InputEvent::Direction(Direction::Up)),
-                    event::KeyCode::Char('s') => return Ok(InputEvent::Direction(Direction::Down)),
-                    event::KeyCode::Char('a') => return Ok(InputEvent::Direction(Direction::Left)),
-                    event::KeyCode::Char('d') => {
-                        return Ok(InputEvent::Direction(Direction::Right))
-                    }
-                    event::KeyCode::Esc => return Ok(InputEvent::Quit),
-                    _ => {}
-                };
-            };
-        }
+        // With clocks running, stop waiting every so often so that the
+        // countdown can be redrawn.
+        let timeout = if self.clocks.is_empty() {
+            None
+        } else {
+            Some(CLOCK_REFRESH)
+        };
+        read_input_event(timeout)
     }
 
     /// Moves and places the cursor on the specified coordinates.
     pub fn set_cursor_to_grid(&mut self, position: &Coordinates) -> crossterm::Result<()> {
-        Self::move_cursor_to_grid(position)?;
-        self.cursor = position.clone();
+        Self::move_cursor_to_screen(&self.grid.screen_coords(position))?;
+        self.cursor = *position;
         Ok(())
     }
 
     /// Moves the cursor on the specified grid coordinates visually.
     pub fn move_cursor_to_grid(position: &Coordinates) -> crossterm::Result<()> {
         let screen_coords = Grid::grid_coords_to_screen_coords(position);
-        Ok(Self::move_cursor_to_screen(&screen_coords)?)
+        Self::move_cursor_to_screen(&screen_coords)
     }
 
     /// Moves the cursor on the specified screen coordinates visually.
@@ -166,93 +880,145 @@ impl TicTacToe {
         Ok(())
     }
 
-    /// Place a character mark on the current position of the cursor.
-    fn mark(&mut self, player: Player) -> crossterm::Result<&Self> {
-        if self.marked_positions.get(&self.cursor).is_none() {
-            self.grid.mark_at(self.cursor, player.to_char())?;
-            self.marked_positions.insert(self.cursor, player);
-            // The cursor automatically increments in x-axis after placing the mark.
-            // Let's bring it back to its original position.
-            Self::move_cursor_to_grid(&self.cursor)?;
-            Ok(self)
+    /// Plays the move on the board and the screen. Under gravity the mark
+    /// drops down the column of its position instead.
+    fn play(&mut self, mut next: Move) -> crossterm::Result<&Self> {
+        if self.board.gravity {
+            next.position = self
+                .board
+                .drop_position(&next.position)
+                .ok_or_else(|| io::Error::other("the column is full"))?;
+        }
+        self.rules.apply(&mut self.board, &next)?;
+        self.record.moves.push(next);
+        if let Some(from) = next.from {
+            Self::move_cursor_to_screen(&self.grid.screen_coords(&from))?;
+            self.grid.mark_at(from, ' ')?;
+        }
+        if self.board.gravity {
+            self.grid.drop_at(next.position, next.symbol.to_char())?;
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "the position has already been marked",
-            ))
+            Self::move_cursor_to_screen(&self.grid.screen_coords(&next.position))?;
+            self.grid.mark_at(next.position, next.symbol.to_char())?;
+        }
+        self.fade_vanishing_marks()?;
+        // The cursor automatically increments in x-axis after placing the mark.
+        // Let's bring it back to its original position.
+        Self::move_cursor_to_screen(&self.grid.screen_coords(&self.cursor))?;
+        Ok(self)
+    }
+
+    /// Fades the mark of every player which disappears with their next move,
+    /// such as their oldest mark under vanishing rules.
+    fn fade_vanishing_marks(&mut self) -> crossterm::Result<()> {
+        for player in Player::ALL[..self.seats.len()].iter() {
+            if let Some(position) = self.rules.vanishing_mark(&self.board, *player) {
+                self.grid.fade_at(position, player.to_char())?;
+            }
         }
+        Ok(())
+    }
+
+    /// Place the symbol on the current position of the cursor.
+    fn mark(&mut self, symbol: Player) -> crossterm::Result<&Self> {
+        self.play(Move::place(self.cursor, symbol))
     }
 
     pub fn mark_cross(&mut self) -> crossterm::Result<&Self> {
-        Ok(self.mark(Player::Cross)?)
+        self.mark(Player::Cross)
     }
 
     pub fn mark_zero(&mut self) -> crossterm::Result<&Self> {
-        Ok(self.mark(Player::Zero)?)
+        self.mark(Player::Zero)
     }
 
     pub fn grid_has_empty_boxes(&self) -> bool {
-        let Side(side) = self.grid.side;
-        self.marked_positions.len() != side.pow(2).into()
+        !self.board.is_full()
     }
 
+    /// Checks whether the player has completed a line. Whether that wins or
+    /// loses the game is up to the rules.
     pub fn check_for_victory(&self, player: &Player) -> bool {
-        let Side(side) = self.grid.side;
-        let mut victory = true;
-
-        // Check if any vertical pattern is complete
-        for x in 0..(side as i16) {
-            victory = true;
-            for y in 0..(side as i16) {
-                if self.marked_positions.get(&Coordinates { x, y }) != Some(player) {
-                    victory = false;
-                    break;
-                }
-            }
-            if victory {
-                return true;
-            }
-        }
+        self.board.has_line(player)
+    }
+}
 
-        // Check if any horizontal pattern is complete
-        for y in 0..(side as i16) {
-            victory = true;
-            for x in 0..(side as i16) {
-                if self.marked_positions.get(&Coordinates { x, y }) != Some(player) {
-                    victory = false;
-                    break;
-                }
-            }
-            if victory {
-                return true;
+/// Read and translate keyboard input to an `InputEvent`. With a timeout, gives
+/// up waiting once it passes and returns `InputEvent::Tick`.
+pub(crate) fn read_input_event(timeout: Option<Duration>) -> crossterm::Result<InputEvent> {
+    loop {
+        if let Some(timeout) = timeout {
+            if !poll(timeout)? {
+                return Ok(InputEvent::Tick);
             }
         }
-
-        // Check if top-left to bottom-right pattern is complete
-        for z in 0..(side as i16) {
-            victory = true;
-            if self.marked_positions.get(&Coordinates { x: z, y: z }) != Some(player) {
-                victory = false;
-                break;
+        if let Event::Key(k) = read()? {
+            if let Some(direction) = key_direction(k.code) {
+                return Ok(InputEvent::Direction(direction));
             }
-        }
-        if victory {
-            return true;
-        }
+            match k.code {
+                event::KeyCode::Enter => return Ok(InputEvent::Mark),
+                event::KeyCode::Tab => return Ok(InputEvent::ToggleSymbol),
+                event::KeyCode::Char(':') => return Ok(InputEvent::Prompt),
+                event::KeyCode::Esc => return Ok(InputEvent::Quit),
+                _ => {}
+            };
+        };
+    }
+}
+
+/// The direction the cursor moves in when the key is pressed, if any: `wasd`
+/// within a layer, and Page Up and Page Down through the layers.
+pub(crate) fn key_direction(code: event::KeyCode) -> Option<Direction> {
+    match code {
+        event::KeyCode::Char('w') => Some(Direction::Up),
+        event::KeyCode::Char('s') => Some(Direction::Down),
+        event::KeyCode::Char('a') => Some(Direction::Left),
+        event::KeyCode::Char('d') => Some(Direction::Right),
+        event::KeyCode::PageUp => Some(Direction::PreviousLayer),
+        event::KeyCode::PageDown => Some(Direction::NextLayer),
+        _ => None,
+    }
+}
 
-        // Check if bottom-left to top-right pattern is complete
-        for x in 0..(side as i16) {
-            victory = true;
-            let y = side as i16 - x - 1;
-            if self.marked_positions.get(&Coordinates { x, y }) != Some(player) {
-                victory = false;
-                break;
+fn invalid_command(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Waits for the player to answer a question with `y` or `n`. Esc answers no.
+pub(crate) fn read_yes_no() -> crossterm::Result<bool> {
+    loop {
+        if let Event::Key(k) = read()? {
+            match k.code {
+                event::KeyCode::Char('y') => return Ok(true),
+                event::KeyCode::Char('n') | event::KeyCode::Esc => return Ok(false),
+                _ => {}
             }
         }
-        if victory {
-            return true;
-        }
-
-        false
     }
 }
+
+/// Prints a message on the given screen row, replacing any previous text.
+pub(crate) fn print_message(row: u16, msg: &str) -> crossterm::Result<()> {
+    // Cleanup any previous text
+    execute!(
+        stdout(),
+        cursor::MoveTo(0, row),
+        SetBackgroundColor(Color::Black),
+        Print(" ".repeat(100)),
+        ResetColor
+    )?;
+    execute!(
+        stdout(),
+        cursor::MoveTo(0, row),
+        SetForegroundColor(Color::Black),
+        SetBackgroundColor(Color::White),
+        Print(msg),
+        ResetColor
+    )?;
+    // Good idea to move the cursor on to the next line since it seems
+    // terminals in raw mode do not put an empty line at the end of STDOUT
+    // by themselves.
+    execute!(stdout(), cursor::MoveTo(0, row + 1))?;
+    Ok(())
+}
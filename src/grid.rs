@@ -1,90 +1,259 @@
-use super::{Coordinates, Side};
+use super::board::Board;
+use super::{column_label, Coordinates, Player, Side};
 
 use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
 use crossterm::{cursor, execute, terminal};
 use std::io;
 use std::io::stdout;
+use std::thread;
+use std::time::Duration;
 
-/// The Grid draws empty boxes and defines the layout for the game.
+/// Number of screen columns left blank between the layers of a 3D grid.
+const LAYER_GAP: u16 = 3;
+
+/// How long a dropped marker is shown on each row it falls past.
+const DROP_STEP: Duration = Duration::from_millis(40);
+
+/// The Grid draws empty boxes and defines the layout for the game. Grids of
+/// several layers draw them side by side, from the first layer on the left.
 #[derive(Debug, PartialEq)]
 pub struct Grid {
-    pub side: Side,
+    pub width: u16,
+    pub height: u16,
+    pub layers: u16,
 }
 
 impl Grid {
     pub fn from(side: Side) -> Self {
-        Self { side: side }
+        Self::layered(side, 1)
+    }
+
+    /// A rectangular grid of a single layer.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            layers: 1,
+        }
+    }
+
+    pub fn layered(side: Side, layers: u16) -> Self {
+        let Side(side) = side;
+        Self {
+            width: side,
+            height: side,
+            layers,
+        }
     }
 
     /// Removes already existing text on the terminal.
-    fn cleanup() -> crossterm::Result<()> {
+    pub(crate) fn cleanup() -> crossterm::Result<()> {
         terminal::enable_raw_mode().unwrap();
         execute!(stdout(), terminal::Clear(terminal::ClearType::All))?;
         Ok(())
     }
 
-    /// Draws a square grid of the specified side.
+    /// Draws a grid of the specified size for every layer, with the columns
+    /// labelled below each layer and the rows to the right of the last one.
     pub fn draw(&mut self) -> crossterm::Result<&mut Self> {
         Self::cleanup()?;
 
-        let Side(side) = self.side;
-        let grid_length = side * 4 - 1;
-        let grid_background = std::iter::repeat(" ")
-            .take(grid_length.into())
-            .collect::<String>();
+        let grid_length = self.width * 4 - 1;
+        let grid_background = " ".repeat(grid_length.into());
 
-        for y in 0..side {
-            execute!(
-                stdout(),
-                cursor::MoveTo(0, y),
-                SetBackgroundColor(Color::White),
-                Print(&grid_background),
-                ResetColor
-            )?;
-            for x in 0..(side - 1) {
-                let boundary_position = x * 4 + 3;
+        for z in 0..self.layers {
+            let offset = self.layer_offset(z as i16) as u16;
+            for y in 0..self.height {
                 execute!(
                     stdout(),
-                    cursor::MoveTo(boundary_position, y),
-                    SetForegroundColor(Color::Black),
+                    cursor::MoveTo(offset, y),
                     SetBackgroundColor(Color::White),
-                    Print("|"),
+                    Print(&grid_background),
                     ResetColor
                 )?;
+                for x in 0..(self.width - 1) {
+                    let boundary_position = offset + x * 4 + 3;
+                    execute!(
+                        stdout(),
+                        cursor::MoveTo(boundary_position, y),
+                        SetForegroundColor(Color::Black),
+                        SetBackgroundColor(Color::White),
+                        Print("|"),
+                        ResetColor
+                    )?;
+                }
+            }
+            for x in 0..self.width {
+                execute!(
+                    stdout(),
+                    cursor::MoveTo(offset + x * 4 + 1, self.height),
+                    Print(column_label(x as i16))
+                )?;
             }
         }
+        let last_layer = self.layer_offset(self.layers as i16 - 1) as u16;
+        for y in 0..self.height {
+            execute!(
+                stdout(),
+                cursor::MoveTo(last_layer + grid_length + 1, y),
+                Print(y + 1)
+            )?;
+        }
+        Ok(self)
+    }
+
+    /// Draws the blocked boxes and the marks of the board on the grid.
+    pub fn draw_position(&mut self, board: &Board) -> crossterm::Result<&mut Self> {
+        for position in board.blocked_positions() {
+            self.block_at(position)?;
+        }
+        for (position, player) in board.marked_positions() {
+            let screen_coords = self.screen_coords(position);
+            execute!(
+                stdout(),
+                cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+            )?;
+            self.mark_at(*position, player.to_char())?;
+        }
         Ok(self)
     }
 
+    /// The first screen row below the grid and its labels, where the game
+    /// shows its clocks and status.
+    pub fn bottom(&self) -> u16 {
+        self.height + 1
+    }
+
     /// Grid coordinates are the coordinates used to locate a box in the grid,
     /// whereas the screen coordinates are based on the actual screen pixels.
     pub fn grid_coords_to_screen_coords(position: &Coordinates) -> Coordinates {
         Coordinates {
             x: position.x * 4 + 1,
             y: position.y,
+            z: 0,
         }
     }
 
+    /// Like `grid_coords_to_screen_coords`, but also finds the layer the
+    /// position lies on.
+    pub fn screen_coords(&self, position: &Coordinates) -> Coordinates {
+        let mut screen_coords = Self::grid_coords_to_screen_coords(position);
+        screen_coords.x += self.layer_offset(position.z);
+        screen_coords
+    }
+
+    /// Screen column where the given layer starts.
+    fn layer_offset(&self, layer: i16) -> i16 {
+        layer * (self.width * 4 - 1 + LAYER_GAP) as i16
+    }
+
     /// Draw a character marker at some specific grid coordinates.
     pub fn mark_at(&mut self, position: Coordinates, marker: char) -> crossterm::Result<&Self> {
-        let Side(side) = &self.side;
         let _position = {
-            if side >= &(position.x as u16) && side >= &(position.y as u16) {
+            if self.width >= position.x as u16 && self.height >= position.y as u16 {
                 Ok(position)
             } else {
-                Err(io::Error::new(
-                    io::ErrorKind::Other,
+                Err(io::Error::other(
                     "position coordinates are out of bounds from the grid area",
                 ))
             }
         }?;
         execute!(
             stdout(),
-            SetForegroundColor(Color::Red),
+            SetForegroundColor(marker_color(marker)),
+            SetBackgroundColor(Color::White),
+            Print(marker),
+            ResetColor
+        )?;
+        Ok(self)
+    }
+
+    /// Draws a character marker at the given grid coordinates on a highlighted
+    /// background, such as a piece which has been picked up.
+    pub fn highlight_at(
+        &mut self,
+        position: Coordinates,
+        marker: char,
+    ) -> crossterm::Result<&Self> {
+        let screen_coords = self.screen_coords(&position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16),
+            SetForegroundColor(marker_color(marker)),
+            SetBackgroundColor(Color::Yellow),
+            Print(marker),
+            ResetColor
+        )?;
+        Ok(self)
+    }
+
+    /// Draws a character marker at the given grid coordinates faded out, such
+    /// as a mark which is about to disappear.
+    pub fn fade_at(&mut self, position: Coordinates, marker: char) -> crossterm::Result<&Self> {
+        let screen_coords = self.screen_coords(&position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16),
+            SetForegroundColor(Color::Grey),
             SetBackgroundColor(Color::White),
             Print(marker),
             ResetColor
         )?;
         Ok(self)
     }
+
+    /// Greys out the box at the given grid coordinates, which can never be
+    /// marked.
+    pub fn block_at(&mut self, position: Coordinates) -> crossterm::Result<&Self> {
+        let screen_coords = self.screen_coords(&position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16 - 1, screen_coords.y as u16),
+            SetBackgroundColor(Color::DarkGrey),
+            Print("   "),
+            ResetColor
+        )?;
+        Ok(self)
+    }
+
+    /// Lets a character marker fall down its column from the top row of the
+    /// grid to the given position.
+    pub fn drop_at(&mut self, position: Coordinates, marker: char) -> crossterm::Result<&Self> {
+        for y in 0..position.y {
+            let screen_coords = self.screen_coords(&Coordinates { y, ..position });
+            let (column, row) = (screen_coords.x as u16, screen_coords.y as u16);
+            execute!(
+                stdout(),
+                cursor::MoveTo(column, row),
+                SetForegroundColor(marker_color(marker)),
+                SetBackgroundColor(Color::White),
+                Print(marker),
+                ResetColor
+            )?;
+            thread::sleep(DROP_STEP);
+            execute!(
+                stdout(),
+                cursor::MoveTo(column, row),
+                SetBackgroundColor(Color::White),
+                Print(" "),
+                ResetColor
+            )?;
+        }
+        let screen_coords = self.screen_coords(&position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+        )?;
+        self.mark_at(position, marker)
+    }
+}
+
+/// The color a player's marker is drawn in.
+fn marker_color(marker: char) -> Color {
+    match Player::from_char(marker) {
+        Some(Player::Zero) => Color::Blue,
+        Some(Player::Plus) => Color::DarkGreen,
+        Some(Player::Star) => Color::Magenta,
+        _ => Color::Red,
+    }
 }
@@ -1,20 +1,56 @@
 pub mod ai;
+pub mod analysis;
+pub mod board;
+pub mod cli;
+pub mod clock;
+pub mod editor;
 pub mod game;
 pub mod grid;
+pub mod mcts;
+pub mod prompt;
+pub mod quantum;
+pub mod rating;
+pub mod record;
+pub mod replay;
+pub mod rules;
+pub mod series;
+pub mod solver;
+pub mod stats;
+pub mod swap;
+pub mod ultimate;
 
 pub use ai::AI;
-pub use game::TicTacToe;
+pub use analysis::{Analysis, VariationTree};
+pub use board::{Board, Symmetry};
+pub use clock::{Clock, TimeControl};
+pub use editor::Editor;
+pub use game::{Seat, TicTacToe};
 pub use grid::Grid;
+pub use prompt::GameCommand;
+pub use quantum::{QuantumBoard, QuantumTicTacToe};
+pub use rating::Ratings;
+pub use record::GameRecord;
+pub use replay::Replay;
+pub use rules::{Rules, Variant};
+pub use series::Series;
+pub use stats::Stats;
+pub use swap::Swap;
+pub use ultimate::{UltimateBoard, UltimateTicTacToe};
 
+use std::fmt;
+use std::io;
 use std::ops::Add;
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Side(pub u16);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Coordinates {
     pub x: i16,
     pub y: i16,
+    /// Layer of a 3D board; always 0 on flat boards.
+    pub z: i16,
 }
 
 impl Add<Coordinates> for Coordinates {
@@ -24,23 +60,174 @@ impl Add<Coordinates> for Coordinates {
         Coordinates {
             x: self.x + rhs.x,
             y: self.y + rhs.y,
+            z: self.z + rhs.z,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Coordinates are written in algebraic notation: the column as a letter,
+/// starting from `a` on the left and going on with `aa`, `ab`, ... past `z`,
+/// followed by the row as a number, starting from 1 at the top. Positions on
+/// any layer but the first add the layer after a slash, as in `b2/3`.
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", column_label(self.x), self.y + 1)?;
+        if self.z > 0 {
+            write!(f, "/{}", self.z + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Coordinates {
+    type Err = io::Error;
+
+    /// Reads coordinates in the notation written by `Display`, in either
+    /// case.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid coordinates: {}", s),
+            )
+        };
+        let (square, layer) = match s.split_once('/') {
+            Some((square, layer)) => (square, Some(layer)),
+            None => (s, None),
+        };
+        let digits = square
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let (column, row) = square.split_at(digits);
+        if column.is_empty() || !column.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(invalid());
+        }
+        let mut x: i32 = 0;
+        for c in column.to_ascii_lowercase().bytes() {
+            x = x
+                .checked_mul(26)
+                .and_then(|x| x.checked_add((c - b'a') as i32 + 1))
+                .ok_or_else(invalid)?;
+        }
+        let one_based = |value: &str| {
+            if !value.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            match value.parse::<i16>() {
+                Ok(value) if value > 0 => Ok(value - 1),
+                _ => Err(invalid()),
+            }
+        };
+        if x > i16::MAX as i32 {
+            return Err(invalid());
+        }
+        Ok(Coordinates {
+            x: x as i16 - 1,
+            y: one_based(row)?,
+            z: layer.map_or(Ok(0), one_based)?,
+        })
+    }
+}
+
+/// The letters a column is written with in algebraic notation.
+pub(crate) fn column_label(x: i16) -> String {
+    let mut label = String::new();
+    let mut x = x as i32 + 1;
+    while x > 0 {
+        x -= 1;
+        label.insert(0, (b'a' + (x % 26) as u8) as char);
+        x /= 26;
+    }
+    label
+}
+
+/// The players of a game, each with their own symbol. Two-player games are
+/// played by crosses and zeros; games of more players bring in pluses and
+/// then stars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Player {
     Zero,
     Cross,
+    Plus,
+    Star,
 }
 
 impl Player {
+    /// Every player in the order they take turns, starting with crosses.
+    pub const ALL: [Player; 4] = [Self::Cross, Self::Zero, Self::Plus, Self::Star];
+
     pub fn to_char(&self) -> char {
         match self {
             Self::Zero => '0',
             Self::Cross => 'X',
+            Self::Plus => '+',
+            Self::Star => '*',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Player> {
+        match c {
+            '0' => Some(Self::Zero),
+            'X' => Some(Self::Cross),
+            '+' => Some(Self::Plus),
+            '*' => Some(Self::Star),
+            _ => None,
+        }
+    }
+
+    /// The other player of a two-player game. Pluses and stars only play in
+    /// games of more players, where the opponent is taken to be crosses.
+    pub fn opponent(&self) -> Player {
+        match self {
+            Self::Cross => Self::Zero,
+            _ => Self::Cross,
         }
     }
+
+    /// Where the player comes in the order of `ALL`.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Cross => 0,
+            Self::Zero => 1,
+            Self::Plus => 2,
+            Self::Star => 3,
+        }
+    }
+
+    /// The player who moves after this one in a game of the given number of
+    /// players.
+    pub fn next(&self, players: usize) -> Player {
+        Self::ALL[(self.index() + 1) % players]
+    }
+}
+
+/// A move: the position marked and the symbol placed on it. Players place
+/// their own symbol, except under rules which let them pick either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub position: Coordinates,
+    pub symbol: Player,
+    /// Where the piece slides from, under rules which move pieces around once
+    /// they are all on the board; `None` when a new mark is placed.
+    pub from: Option<Coordinates>,
+}
+
+impl Move {
+    /// A move which places a new mark.
+    pub fn place(position: Coordinates, symbol: Player) -> Self {
+        Self {
+            position,
+            symbol,
+            from: None,
+        }
+    }
+}
+
+/// How a finished game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Win(Player),
+    Draw,
 }
 
 #[derive(Debug, PartialEq)]
@@ -48,6 +235,12 @@ pub enum InputEvent {
     Direction(Direction),
     Mark,
     Quit,
+    /// Switch the symbol the player places, under rules which allow either.
+    ToggleSymbol,
+    /// No input arrived for a while; used to keep the clocks ticking.
+    Tick,
+    /// Open the prompt to type a move or a command.
+    Prompt,
 }
 
 #[derive(Debug, PartialEq)]
@@ -59,15 +252,21 @@ pub enum Direction {
     Down,
     Left,
     Right,
+    /// Towards the first layer of a 3D board.
+    PreviousLayer,
+    /// Towards the last layer of a 3D board.
+    NextLayer,
 }
 
 impl Direction {
     pub fn get_relative_coords(&self) -> Coordinates {
         match &self {
-            Direction::Up => Coordinates { x: 0, y: -1 },
-            Direction::Down => Coordinates { x: 0, y: 1 },
-            Direction::Left => Coordinates { x: -1, y: 0 },
-            Direction::Right => Coordinates { x: 1, y: 0 },
+            Direction::Up => Coordinates { x: 0, y: -1, z: 0 },
+            Direction::Down => Coordinates { x: 0, y: 1, z: 0 },
+            Direction::Left => Coordinates { x: -1, y: 0, z: 0 },
+            Direction::Right => Coordinates { x: 1, y: 0, z: 0 },
+            Direction::PreviousLayer => Coordinates { x: 0, y: 0, z: -1 },
+            Direction::NextLayer => Coordinates { x: 0, y: 0, z: 1 },
             // _ => panic!("diagonal movement is not yet implemented!"),
         }
     }
@@ -1,11 +1,391 @@
-use crossterm::Result;
-use tictactoe::{Grid, Side, TicTacToe, AI};
+use crossterm::{terminal, Result};
+use std::fs;
+use tictactoe::ai::DEFAULT_THINK_TIME;
+use tictactoe::cli::{Command, GameOptions, Mode};
+use tictactoe::editor::EditorChoice;
+use tictactoe::rating;
+use tictactoe::series::{ask_next_game, game_path, print_lines, read_line, NextGame};
+use tictactoe::stats::{self, GameResult, PlayedGame, DEFAULT_PROFILE};
+use tictactoe::{
+    solver, Analysis, Board, Editor, GameCommand, GameRecord, Grid, Outcome, Player,
+    QuantumTicTacToe, Ratings, Replay, Seat, Series, Stats, TicTacToe, UltimateTicTacToe, AI,
+};
 
 fn main() -> Result<()> {
-    // let mut grid: Grid = Default::default();
-    let mut grid = Grid::from(Side(3));
+    match Command::parse(std::env::args().skip(1))? {
+        Command::Play(mut options) => {
+            choose_ai(&mut options)?;
+            match options.mode {
+                Mode::Ultimate => play_ultimate(options),
+                Mode::Quantum => play_quantum(options),
+                Mode::Classic | Mode::Qubic => play_session(options, 0),
+            }
+        }
+        Command::Solve {
+            width,
+            height,
+            win_length,
+        } => {
+            println!("{}", solver::solve(width, height, win_length));
+            Ok(())
+        }
+        Command::Stats { profile } => {
+            for line in load_stats(&profile)?.report(&profile) {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+        Command::Edit(options) => edit(options),
+        Command::Replay { file, speed } => {
+            let record: GameRecord = fs::read_to_string(file)?.parse()?;
+            let mut replay = Replay::new(record)?;
+            replay.speed = speed;
+            replay.show()
+        }
+        Command::Analyze { file, think_time } => {
+            let record: GameRecord = fs::read_to_string(&file)?.parse()?;
+            let mut analysis = Analysis::new(record)?;
+            analysis.path = Some(file);
+            if let Some(think_time) = think_time {
+                analysis.think_time = think_time;
+            }
+            analysis.show()
+        }
+        Command::Leaderboard => {
+            let lines = load_ratings()?.leaderboard();
+            if lines.is_empty() {
+                println!("No rated games yet");
+            }
+            for line in lines {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Plays one match after another until the player quits, and then prints
+/// how each of them went. Every game draws its AI's random numbers from the
+/// seed after the previous game's, and is saved to a file of its own,
+/// numbered after the games already played.
+fn play_session(mut options: GameOptions, mut games: usize) -> Result<()> {
+    let mut seed = options.seed.unwrap_or_else(rand::random);
+    let mut series = new_series(&mut options)?;
+    let mut finished = Vec::new();
+    'session: loop {
+        let tictactoe = play(&options, &series, seed, None)?;
+        seed = seed.wrapping_add(1);
+        games += 1;
+        if let Some(path) = &options.save {
+            fs::write(game_path(path, games), tictactoe.record.to_string())?;
+        }
+        // The AI may have been switched at the prompt.
+        options.ai = tictactoe.ai_algo;
+        let outcome = match (tictactoe.outcome, &tictactoe.requested) {
+            (Some(outcome), _) => outcome,
+            (None, Some(GameCommand::New(board))) => {
+                resize(&mut options, *board);
+                finished.push(series);
+                series = new_series(&mut options)?;
+                continue;
+            }
+            (None, _) => break,
+        };
+        series.record(outcome);
+        record_game(&options, &tictactoe, outcome)?;
+        tictactoe.footer(&series.scoreboard())?;
+        let row = tictactoe.grid.bottom() + 3;
+        let new_options = loop {
+            match ask_next_game(row, series.is_over())? {
+                NextGame::Rematch => break None,
+                NextGame::NewSettings => break ask_settings(row)?,
+                NextGame::Stats => {
+                    let profile = profile(&options);
+                    print_lines(row + 1, &load_stats(profile)?.report(profile))?;
+                }
+                NextGame::Quit => break 'session,
+            }
+        };
+        if let Some(new_options) = new_options {
+            options = new_options;
+            finished.push(series);
+            series = new_series(&mut options)?;
+        } else if series.is_over() {
+            finished.push(series);
+            series = new_series(&mut options)?;
+        }
+    }
+    finished.push(series);
+    terminal::disable_raw_mode()?;
+    println!();
+    for series in finished.iter().filter(|series| !series.results.is_empty()) {
+        print!("{}", series.summary());
+    }
+    Ok(())
+}
+
+/// A match with the given options, where the players take turns moving
+/// first, or opening under a swap rule. The AI is picked anew for every
+/// match when it is matched to the player's rating.
+fn new_series(options: &mut GameOptions) -> Result<Series> {
+    choose_ai(options)?;
+    let seats = options
+        .seats
+        .clone()
+        .unwrap_or_else(|| vec![Seat::Human, Seat::Ai]);
+    let mut series = Series::new(options.best_of.unwrap_or(1), seats);
+    series.rotate_seats = options.swap.is_none();
+    Ok(series)
+}
+
+/// Picks the AI whose rating is closest to the player's, among those which
+/// can play the game, if asked to.
+fn choose_ai(options: &mut GameOptions) -> Result<()> {
+    if !options.auto_ai {
+        return Ok(());
+    }
+    let players = options.seats.as_ref().map_or(2, Vec::len);
+    let ais: Vec<AI> = AI::ALL
+        .iter()
+        .copied()
+        .filter(|ai| match options.mode {
+            Mode::Ultimate => ai.supports_ultimate(),
+            Mode::Quantum => ai.supports_quantum(),
+            Mode::Classic | Mode::Qubic => players == 2 || *ai != AI::MiniMax,
+        })
+        .collect();
+    let ratings = load_ratings()?;
+    let think_time = options.ai_time.unwrap_or(DEFAULT_THINK_TIME);
+    if let Some(ai) = ratings.closest_ai(profile(options), &ais, think_time) {
+        options.ai = ai;
+    }
+    Ok(())
+}
+
+/// The profile whose statistics the games are counted in.
+fn profile(options: &GameOptions) -> &str {
+    options.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+}
+
+/// Reads the statistics of the given profile; there are none if there is
+/// nowhere to keep them.
+fn load_stats(profile: &str) -> Result<Stats> {
+    match stats::profile_path(profile) {
+        Some(path) => Stats::load(&path),
+        None => Ok(Stats::default()),
+    }
+}
+
+/// Reads the ratings of every profile and AI; there are none if there is
+/// nowhere to keep them.
+fn load_ratings() -> Result<Ratings> {
+    match rating::ratings_path() {
+        Some(path) => Ratings::load(&path),
+        None => Ok(Ratings::default()),
+    }
+}
+
+/// Counts a finished game in the player's statistics and rates it. Only
+/// games between a single person and the AI are counted.
+fn record_game(options: &GameOptions, tictactoe: &TicTacToe, outcome: Outcome) -> Result<()> {
+    let humans = tictactoe
+        .seats
+        .iter()
+        .filter(|seat| **seat == Seat::Human)
+        .count();
+    if humans != 1 {
+        return Ok(());
+    }
+    let path = match stats::profile_path(profile(options)) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let result = match outcome {
+        Outcome::Win(player) if tictactoe.seats[player.index()] == Seat::Human => GameResult::Win,
+        Outcome::Win(_) => GameResult::Loss,
+        Outcome::Draw => GameResult::Draw,
+    };
+    let board = &tictactoe.board;
+    let board = if board.depth > 1 {
+        format!(
+            "{}x{}x{}x{}",
+            board.width, board.height, board.depth, board.win_length
+        )
+    } else {
+        format!("{}x{}x{}", board.width, board.height, board.win_length)
+    };
+    let game = PlayedGame {
+        opponent: tictactoe.ai_algo.to_string(),
+        board,
+        result,
+        moves: tictactoe.record.moves.len(),
+    };
+    Stats::append(&path, &game)?;
+    if let Some(path) = rating::ratings_path() {
+        let mut ratings = Ratings::load(&path)?;
+        ratings.update(
+            profile(options),
+            &rating::ai_name(tictactoe.ai_algo, tictactoe.ai_time),
+            result,
+        );
+        ratings.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Asks for new settings, written as on the command line, until they can
+/// be played in a match. Returns `None` if the player keeps the old ones.
+fn ask_settings(row: u16) -> Result<Option<GameOptions>> {
+    let mut prompt = "New settings: ".to_string();
+    loop {
+        let line = match read_line(row, &prompt)? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+        let error = match GameOptions::parse(line.split_whitespace().map(String::from)) {
+            Ok(options) if matches!(options.mode, Mode::Classic | Mode::Qubic) => {
+                return Ok(Some(options))
+            }
+            Ok(_) => "only classic and qubic games can be played in a match".to_string(),
+            Err(e) => e.to_string(),
+        };
+        prompt = format!("{}; new settings: ", error);
+    }
+}
+
+/// Plays the next games on a flat board of the given size, asked for by
+/// typing `:new` in a game, or else on the same board.
+fn resize(options: &mut GameOptions, board: Option<(u16, u16, u16)>) {
+    if let Some(board) = board {
+        options.board = Some(board);
+        options.map = None;
+        options.mode = Mode::Classic;
+    }
+}
+
+/// The empty board the options call for: read from a map if there is one,
+/// the cube of qubic, or a flat board of the size given.
+fn new_board(options: &GameOptions) -> Result<Board> {
+    let mut board = match &options.map {
+        Some(path) => Board::from_map(&fs::read_to_string(path)?)?,
+        None if options.mode == Mode::Qubic => Board::layered(4, 4, 4, 4),
+        None => {
+            let (width, height, win_length) = options
+                .board
+                .unwrap_or_else(|| options.rules.default_board());
+            Board::new(width, height, win_length)
+        }
+    };
+    board.gravity = options.gravity;
+    board.torus = options.torus;
+    Ok(board)
+}
+
+/// Plays the next game of the match, from the start or from a position set
+/// up in the editor.
+fn play(
+    options: &GameOptions,
+    series: &Series,
+    seed: u64,
+    start: Option<(Board, Player)>,
+) -> Result<TicTacToe> {
+    let (board, to_move) = match start {
+        Some(start) => start,
+        None => (new_board(options)?, Player::Cross),
+    };
+    let mut grid = Grid {
+        width: board.width,
+        height: board.height,
+        layers: board.depth,
+    };
     grid.draw()?;
-    let mut tictactoe = TicTacToe::from(grid, AI::Random).expect("could not initialize game");
+    let mut tictactoe =
+        TicTacToe::from_seed(grid, options.ai, seed).expect("could not initialize game");
+    tictactoe.set_position(board, to_move);
+    tictactoe.set_rules(options.rules);
+    tictactoe.set_seats(series.seats_for_next_game());
+    if let Some(swap) = options.swap {
+        // The player and the AI take turns opening.
+        let ai_opens = options.ai_opens != (series.results.len() % 2 == 1);
+        let opener = if ai_opens {
+            Player::Zero
+        } else {
+            Player::Cross
+        };
+        tictactoe.set_swap(swap, opener);
+    }
+    if let Some(ai_time) = options.ai_time {
+        tictactoe.ai_time = ai_time;
+    }
+    if let Some(time_control) = options.clock {
+        tictactoe.set_time_control(time_control);
+    }
+    if series.best_of > 1 {
+        tictactoe.footer(&series.scoreboard())?;
+    }
     tictactoe.game_loop()?;
-    Ok(())
+    Ok(tictactoe)
+}
+
+/// Sets up a position in the editor, and then plays a game from it or
+/// analyzes it, saving the game or the analysis to the file given by
+/// `--save`.
+fn edit(mut options: GameOptions) -> Result<()> {
+    let mut editor = Editor::new(new_board(&options)?);
+    let choice = editor.show(&options.rules)?;
+    let seed = options.seed.unwrap_or_else(rand::random);
+    match choice {
+        EditorChoice::Play => {
+            let series = new_series(&mut options)?;
+            let tictactoe = play(
+                &options,
+                &series,
+                seed,
+                Some((editor.board, editor.to_move)),
+            )?;
+            if let Some(path) = &options.save {
+                fs::write(path, tictactoe.record.to_string())?;
+            }
+            if let Some(GameCommand::New(board)) = tictactoe.requested {
+                options.ai = tictactoe.ai_algo;
+                resize(&mut options, board);
+                return play_session(options, 1);
+            }
+            terminal::disable_raw_mode()?;
+            println!();
+            Ok(())
+        }
+        EditorChoice::Analyze => {
+            let mut record = GameRecord::from_position(&editor.board, editor.to_move, seed);
+            record.rules = options.rules.to_string();
+            let mut analysis = Analysis::new(record)?;
+            analysis.path = options.save;
+            if let Some(ai_time) = options.ai_time {
+                analysis.think_time = ai_time;
+            }
+            analysis.show()
+        }
+        EditorChoice::Quit => {
+            terminal::disable_raw_mode()?;
+            Ok(())
+        }
+    }
+}
+
+fn play_ultimate(options: GameOptions) -> Result<()> {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut ultimate = UltimateTicTacToe::from_seed(options.ai, seed);
+    if let Some(ai_time) = options.ai_time {
+        ultimate.ai_time = ai_time;
+    }
+    ultimate.game_loop()
+}
+
+fn play_quantum(options: GameOptions) -> Result<()> {
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut quantum = QuantumTicTacToe::from_seed(options.ai, seed);
+    if let Some(ai_time) = options.ai_time {
+        quantum.ai_time = ai_time;
+    }
+    quantum.game_loop()
 }
@@ -0,0 +1,158 @@
+use super::{Outcome, Player};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::Instant;
+
+/// Upper bound on the number of playouts per move, so that searches which
+/// finish before their deadline are reproducible from a seed.
+pub const MAX_ITERATIONS: u32 = 20_000;
+
+/// Number of random moves after which a playout is scored as a draw, since
+/// games where marks move or vanish may never end by themselves.
+const MAX_PLAYOUT_MOVES: usize = 1_000;
+
+/// Exploration constant of the UCT formula.
+const EXPLORATION: f64 = 1.41;
+
+/// A game position which Monte Carlo tree search can play moves on.
+pub trait Playable: Clone {
+    /// What a single move consists of, such as the position to mark.
+    type Move: Copy;
+
+    /// The player whose turn it is.
+    fn to_move(&self) -> Player;
+    /// Every move the player to move may make.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+    /// Plays a legal move for the player to move.
+    fn play(&mut self, next: Self::Move);
+    /// How the game ended, or `None` if it goes on.
+    fn outcome(&self) -> Option<Outcome>;
+}
+
+struct Node<M> {
+    /// The move leading to this node.
+    played: Option<M>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<M>,
+    /// The player who made the move leading to this node.
+    player: Player,
+    visits: u32,
+    /// Sum of the playout results from the point of view of `player`: one for
+    /// a win and a half for a draw.
+    score: f64,
+}
+
+/// Picks a move for the player to move by Monte Carlo tree search: random
+/// playouts are run until the deadline passes or `MAX_ITERATIONS` is reached,
+/// and the move which was explored the most is played.
+pub fn search<P: Playable, R: Rng + ?Sized>(root: &P, rng: &mut R, deadline: Instant) -> P::Move {
+    search_with_value(root, rng, deadline).0
+}
+
+/// Like `search`, but also tells how well the move did in the playouts: the
+/// share of them the player to move won, with draws counting half.
+pub fn search_with_value<P: Playable, R: Rng + ?Sized>(
+    root: &P,
+    rng: &mut R,
+    deadline: Instant,
+) -> (P::Move, f64) {
+    let mut untried = root.legal_moves();
+    untried.shuffle(rng);
+    let mut nodes = vec![Node {
+        played: None,
+        parent: None,
+        children: Vec::new(),
+        untried,
+        player: root.to_move().opponent(),
+        visits: 0,
+        score: 0.0,
+    }];
+    for _ in 0..MAX_ITERATIONS {
+        if Instant::now() >= deadline && !nodes[0].children.is_empty() {
+            break;
+        }
+        let mut state = root.clone();
+        let mut node = 0;
+
+        // Selection: walk down through fully expanded nodes.
+        while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+            node = select_child(&nodes, node);
+            state.play(nodes[node].played.unwrap());
+        }
+
+        // Expansion: add one of the moves which was not tried yet.
+        if let Some(next) = nodes[node].untried.pop() {
+            let player = state.to_move();
+            state.play(next);
+            let mut untried = if state.outcome().is_none() {
+                state.legal_moves()
+            } else {
+                Vec::new()
+            };
+            untried.shuffle(rng);
+            nodes.push(Node {
+                played: Some(next),
+                parent: Some(node),
+                children: Vec::new(),
+                untried,
+                player,
+                visits: 0,
+                score: 0.0,
+            });
+            let child = nodes.len() - 1;
+            nodes[node].children.push(child);
+            node = child;
+        }
+
+        // Simulation: play random moves until the game is over.
+        let mut playout_moves = 0;
+        let outcome = loop {
+            if let Some(outcome) = state.outcome() {
+                break outcome;
+            }
+            if playout_moves == MAX_PLAYOUT_MOVES {
+                break Outcome::Draw;
+            }
+            let next = *state.legal_moves().choose(rng).unwrap();
+            state.play(next);
+            playout_moves += 1;
+        };
+
+        // Backpropagation.
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut nodes[index];
+            node.visits += 1;
+            node.score += match outcome {
+                Outcome::Win(winner) if winner == node.player => 1.0,
+                Outcome::Win(_) => 0.0,
+                Outcome::Draw => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+    let best = nodes[0]
+        .children
+        .iter()
+        .max_by_key(|child| nodes[**child].visits)
+        .unwrap();
+    let best = &nodes[*best];
+    (best.played.unwrap(), best.score / f64::from(best.visits))
+}
+
+/// The child with the highest upper confidence bound.
+fn select_child<M>(nodes: &[Node<M>], node: usize) -> usize {
+    let parent_visits = f64::from(nodes[node].visits).ln();
+    let uct = |child: usize| {
+        let child = &nodes[child];
+        let visits = f64::from(child.visits);
+        child.score / visits + EXPLORATION * (parent_visits / visits).sqrt()
+    };
+    *nodes[node]
+        .children
+        .iter()
+        .max_by(|a, b| uct(**a).partial_cmp(&uct(**b)).unwrap())
+        .unwrap()
+}
@@ -0,0 +1,102 @@
+use super::ai::AI;
+use super::cli::parse_board_config;
+use super::Coordinates;
+
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Every command which can be typed at the prompt, besides a position to mark.
+pub const COMMANDS: [&str; 5] = ["ai", "hint", "new", "save", "undo"];
+
+/// What the player typed at the `:` prompt of a game: a position to mark, in
+/// algebraic notation such as `b2`, or one of the commands:
+///
+/// ```text
+/// undo              take back your latest move, and the AI's replies to it
+/// new [W H [K]]     start a new game, on a board of the given size
+/// ai NAME           let another AI play: random, minimax or mcts
+/// save FILE         write the record of the game so far to the file
+/// hint              ask the engine for the best move
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameCommand {
+    Mark(Coordinates),
+    Undo,
+    /// Starts a new game, on a flat board of the given width, height and win
+    /// length, or else on the same board.
+    New(Option<(u16, u16, u16)>),
+    Ai(AI),
+    Save(PathBuf),
+    Hint,
+}
+
+impl FromStr for GameCommand {
+    type Err = io::Error;
+
+    /// Reads a command as typed, with or without the leading colon.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix(':').unwrap_or(s);
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        match fields[..] {
+            ["undo"] => Ok(Self::Undo),
+            ["hint"] => Ok(Self::Hint),
+            ["new"] => Ok(Self::New(None)),
+            ["new", ref size @ ..] if size.len() <= 3 => {
+                Ok(Self::New(Some(parse_board_config(&size.join("x"))?)))
+            }
+            ["ai", name] => Ok(Self::Ai(name.parse()?)),
+            ["ai"] => Err(invalid_command("ai needs one of random, minimax, mcts")),
+            ["save", file] => Ok(Self::Save(PathBuf::from(file))),
+            ["save"] => Err(invalid_command("save needs a file name")),
+            [position] => position
+                .parse()
+                .map(Self::Mark)
+                .map_err(|_| invalid_command(&format!("unknown command: {}", position))),
+            _ => Err(invalid_command(&format!("unknown command: {}", s))),
+        }
+    }
+}
+
+/// Completes the last word of the line typed so far as far as the commands
+/// starting with it agree, or the name of the AI after `ai`. A word only one
+/// command starts with is completed with a space after it.
+pub fn complete(line: &str) -> String {
+    let (done, word) = match line.rsplit_once(' ') {
+        Some((done, word)) => (format!("{} ", done), word),
+        None => (String::new(), line),
+    };
+    let ais: Vec<String> = AI::ALL.iter().map(AI::to_string).collect();
+    let candidates: Vec<&str> = match done.trim() {
+        "" => COMMANDS.to_vec(),
+        "ai" => ais.iter().map(String::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let matching: Vec<&str> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect();
+    match matching[..] {
+        [] => line.to_string(),
+        [only] => format!("{}{} ", done, only),
+        _ => format!("{}{}", done, common_prefix(&matching)),
+    }
+}
+
+/// The longest start all the words share.
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let first = words[0];
+    let length = words[1..].iter().fold(first.len(), |length, word| {
+        first[..length]
+            .char_indices()
+            .zip(word.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(length.min(word.len()), |((i, _), _)| i)
+    });
+    &first[..length]
+}
+
+fn invalid_command(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
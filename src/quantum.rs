@@ -0,0 +1,539 @@
+use super::ai::{AI, DEFAULT_THINK_TIME};
+use super::board::Board;
+use super::game::{print_message, read_input_event};
+use super::grid::Grid;
+use super::mcts::Playable;
+use super::{Coordinates, InputEvent, Outcome, Player, Side};
+
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::io;
+use std::io::stdout;
+use std::time::{Duration, Instant};
+
+/// Number of squares along each side of the board.
+pub const SIDE: i16 = 3;
+
+/// A spooky mark: a mark which is in two squares at once until it collapses
+/// into one of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpookyMark {
+    pub player: Player,
+    /// The turn the mark was placed on, counting from 1.
+    pub turn: u16,
+    pub squares: [Coordinates; 2],
+}
+
+impl SpookyMark {
+    /// The square of the mark other than the given one.
+    pub fn other_square(&self, square: &Coordinates) -> Coordinates {
+        if self.squares[0] == *square {
+            self.squares[1]
+        } else {
+            self.squares[0]
+        }
+    }
+}
+
+/// A move in quantum tic-tac-toe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuantumMove {
+    /// Places a spooky mark in two squares.
+    Spooky(Coordinates, Coordinates),
+    /// Resolves the cycle closed by the last spooky mark, collapsing that mark
+    /// into the given square.
+    Collapse(Coordinates),
+    /// Places a classical mark in the only square left, which is all the last
+    /// move of a game may do.
+    Place(Coordinates),
+}
+
+/// Goff's quantum tic-tac-toe on a 3x3 board. Every turn places a spooky mark
+/// in two squares, entangling them. Once the entanglement graph of the squares
+/// gets a cycle, the opponent of the player who closed it picks which of its
+/// two squares the closing mark collapses into. That forces every mark
+/// entangled with it into a single square as well, turning them all into
+/// classical marks, and only classical marks make lines.
+///
+/// When a collapse gives both players a line, the player whose line was
+/// completed first, going by the highest turn number in it, wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantumBoard {
+    /// Spooky marks which have not collapsed yet, in the order they were placed.
+    pub spooky: Vec<SpookyMark>,
+    /// The marks which have collapsed.
+    pub classical: Board,
+    /// The turn each classical mark was placed on.
+    pub turns: HashMap<Coordinates, u16>,
+    /// The spooky mark which closed a cycle, which the player to move has to
+    /// collapse before making their own move.
+    pub pending: Option<SpookyMark>,
+    pub to_move: Player,
+    /// The number of the next turn, counting from 1.
+    pub turn: u16,
+    outcome: Option<Outcome>,
+}
+
+impl Default for QuantumBoard {
+    fn default() -> Self {
+        Self {
+            spooky: Vec::new(),
+            classical: Board::from(Side(3)),
+            turns: HashMap::new(),
+            pending: None,
+            to_move: Player::Cross,
+            turn: 1,
+            outcome: None,
+        }
+    }
+}
+
+impl QuantumBoard {
+    /// The spooky marks in a square, oldest first.
+    pub fn spooky_marks_at(&self, square: &Coordinates) -> Vec<SpookyMark> {
+        self.spooky
+            .iter()
+            .filter(|mark| mark.squares.contains(square))
+            .copied()
+            .collect()
+    }
+
+    /// Squares which have not collapsed into a classical mark yet.
+    pub fn open_squares(&self) -> Vec<Coordinates> {
+        self.classical.empty_positions()
+    }
+
+    pub fn is_legal(&self, next: &QuantumMove) -> bool {
+        self.legal_moves().iter().any(|legal| match (legal, next) {
+            // A spooky mark is the same whichever order its squares are given in.
+            (QuantumMove::Spooky(a, b), QuantumMove::Spooky(c, d)) => {
+                (a, b) == (c, d) || (a, b) == (d, c)
+            }
+            _ => legal == next,
+        })
+    }
+
+    /// Plays a move for the player to move. Spooky marks and classical marks
+    /// pass the turn, while collapsing a cycle leaves the player who resolved it
+    /// to make their own move.
+    pub fn mark(&mut self, next: QuantumMove) -> io::Result<()> {
+        if !self.is_legal(&next) {
+            return Err(io::Error::other("the move cannot be played"));
+        }
+        let player = self.to_move;
+        match next {
+            QuantumMove::Spooky(a, b) => {
+                let mark = SpookyMark {
+                    player,
+                    turn: self.turn,
+                    squares: [a, b],
+                };
+                if self.entangled(&a, &b) {
+                    self.pending = Some(mark);
+                }
+                self.spooky.push(mark);
+                self.turn += 1;
+                self.to_move = player.opponent();
+            }
+            QuantumMove::Collapse(square) => {
+                let mark = self.pending.take().expect("a legal collapse has a cycle");
+                self.collapse(mark, square)?;
+                self.update_outcome();
+            }
+            QuantumMove::Place(square) => {
+                self.classical.mark(square, player)?;
+                self.turns.insert(square, self.turn);
+                self.turn += 1;
+                self.to_move = player.opponent();
+                self.update_outcome();
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether two squares are connected through spooky marks, so that a mark
+    /// placed in both would close a cycle.
+    fn entangled(&self, from: &Coordinates, to: &Coordinates) -> bool {
+        let mut seen = vec![*from];
+        let mut queue = vec![*from];
+        while let Some(square) = queue.pop() {
+            if square == *to {
+                return true;
+            }
+            for mark in self.spooky_marks_at(&square) {
+                let other = mark.other_square(&square);
+                if !seen.contains(&other) {
+                    seen.push(other);
+                    queue.push(other);
+                }
+            }
+        }
+        false
+    }
+
+    /// Turns the mark into a classical mark in the given square. Every other
+    /// spooky mark in that square is pushed out into its other square, which in
+    /// turn pushes out the marks there, until the whole entangled group has
+    /// collapsed.
+    fn collapse(&mut self, mark: SpookyMark, square: Coordinates) -> io::Result<()> {
+        let mut queue = vec![(mark, square)];
+        while let Some((mark, square)) = queue.pop() {
+            self.spooky.retain(|spooky| *spooky != mark);
+            self.classical.mark(square, mark.player)?;
+            self.turns.insert(square, mark.turn);
+            for displaced in self.spooky_marks_at(&square) {
+                self.spooky.retain(|spooky| *spooky != displaced);
+                queue.push((displaced, displaced.other_square(&square)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The highest turn number in the earliest completed line of the player.
+    fn completed_line_turn(&self, player: Player) -> Option<u16> {
+        self.classical
+            .lines()
+            .iter()
+            .filter(|line| {
+                line.iter()
+                    .all(|square| self.classical.get(square) == Some(&player))
+            })
+            .map(|line| line.iter().map(|square| self.turns[square]).max().unwrap())
+            .min()
+    }
+
+    fn update_outcome(&mut self) {
+        self.outcome = match (
+            self.completed_line_turn(Player::Cross),
+            self.completed_line_turn(Player::Zero),
+        ) {
+            (Some(cross), Some(zero)) if zero < cross => Some(Outcome::Win(Player::Zero)),
+            (Some(_), _) => Some(Outcome::Win(Player::Cross)),
+            (None, Some(_)) => Some(Outcome::Win(Player::Zero)),
+            (None, None) if self.classical.is_full() => Some(Outcome::Draw),
+            (None, None) => None,
+        };
+    }
+}
+
+impl Playable for QuantumBoard {
+    type Move = QuantumMove;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<QuantumMove> {
+        if self.outcome.is_some() {
+            return Vec::new();
+        }
+        if let Some(mark) = self.pending {
+            return mark
+                .squares
+                .iter()
+                .map(|square| QuantumMove::Collapse(*square))
+                .collect();
+        }
+        let open = self.open_squares();
+        if let [square] = open[..] {
+            return vec![QuantumMove::Place(square)];
+        }
+        let mut moves = Vec::new();
+        for (index, a) in open.iter().enumerate() {
+            for b in open[index + 1..].iter() {
+                moves.push(QuantumMove::Spooky(*a, *b));
+            }
+        }
+        moves
+    }
+
+    fn play(&mut self, next: QuantumMove) {
+        self.mark(next).expect("only legal moves are played");
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
+
+/// Writes a turn number in subscript digits.
+fn subscript(turn: u16) -> String {
+    turn.to_string()
+        .chars()
+        .map(|digit| std::char::from_u32(0x2080 + digit.to_digit(10).unwrap()).unwrap())
+        .collect()
+}
+
+/// Draws the board with squares big enough to hold every spooky mark placed in
+/// them, three to a row, each with its turn number in subscript.
+#[derive(Debug, Default, PartialEq)]
+pub struct QuantumGrid;
+
+impl QuantumGrid {
+    /// Width of a square on the screen, not counting the boundary after it.
+    const SQUARE_WIDTH: i16 = 11;
+    /// Height of a square on the screen, not counting the boundary below it.
+    const SQUARE_HEIGHT: i16 = 3;
+    const MARKS_PER_ROW: usize = 3;
+
+    /// Screen coordinates of the top-left corner of a square.
+    fn square_origin(position: &Coordinates) -> Coordinates {
+        Coordinates {
+            x: position.x * (Self::SQUARE_WIDTH + 1),
+            y: position.y * (Self::SQUARE_HEIGHT + 1),
+            z: 0,
+        }
+    }
+
+    /// Screen coordinates of the middle of a square, where the cursor goes.
+    pub fn grid_coords_to_screen_coords(position: &Coordinates) -> Coordinates {
+        Self::square_origin(position)
+            + Coordinates {
+                x: Self::SQUARE_WIDTH / 2,
+                y: Self::SQUARE_HEIGHT / 2,
+                z: 0,
+            }
+    }
+
+    /// Screen row below the whole board, used for messages.
+    pub fn message_row() -> u16 {
+        (SIDE * (Self::SQUARE_HEIGHT + 1)) as u16
+    }
+
+    /// Draws the board, highlighting the given squares.
+    pub fn draw(&self, board: &QuantumBoard, highlighted: &[Coordinates]) -> crossterm::Result<()> {
+        let width = (SIDE * (Self::SQUARE_WIDTH + 1) - 1) as usize;
+        let boundary = vec!["-".repeat(Self::SQUARE_WIDTH as usize); SIDE as usize].join("+");
+        for y in 0..SIDE {
+            for x in 0..SIDE {
+                let square = Coordinates { x, y, z: 0 };
+                self.draw_square(board, &square, highlighted.contains(&square))?;
+            }
+            let origin = Self::square_origin(&Coordinates { x: 0, y, z: 0 });
+            for row in 0..Self::SQUARE_HEIGHT {
+                for x in 1..SIDE {
+                    execute!(
+                        stdout(),
+                        cursor::MoveTo(
+                            (x * (Self::SQUARE_WIDTH + 1) - 1) as u16,
+                            (origin.y + row) as u16
+                        ),
+                        SetForegroundColor(Color::Black),
+                        SetBackgroundColor(Color::White),
+                        Print("|"),
+                        ResetColor
+                    )?;
+                }
+            }
+            if y < SIDE - 1 {
+                execute!(
+                    stdout(),
+                    cursor::MoveTo(0, (origin.y + Self::SQUARE_HEIGHT) as u16),
+                    SetForegroundColor(Color::Black),
+                    SetBackgroundColor(Color::White),
+                    Print(format!("{:<1$}", boundary, width)),
+                    ResetColor
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_square(
+        &self,
+        board: &QuantumBoard,
+        square: &Coordinates,
+        highlighted: bool,
+    ) -> crossterm::Result<()> {
+        let origin = Self::square_origin(square);
+        let background = if highlighted {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        for row in 0..Self::SQUARE_HEIGHT {
+            execute!(
+                stdout(),
+                cursor::MoveTo(origin.x as u16, (origin.y + row) as u16),
+                SetBackgroundColor(background),
+                Print(" ".repeat(Self::SQUARE_WIDTH as usize)),
+            )?;
+        }
+        if let Some(player) = board.classical.get(square) {
+            let center = Self::grid_coords_to_screen_coords(square);
+            execute!(
+                stdout(),
+                cursor::MoveTo(center.x as u16, center.y as u16),
+                SetForegroundColor(Color::Red),
+                Print(player.to_char()),
+                Print(subscript(board.turns[square])),
+                ResetColor
+            )?;
+            return Ok(());
+        }
+        let marks = board.spooky_marks_at(square);
+        for (row, marks) in marks.chunks(Self::MARKS_PER_ROW).enumerate() {
+            execute!(
+                stdout(),
+                cursor::MoveTo(origin.x as u16, (origin.y + row as i16) as u16),
+                SetForegroundColor(Color::Black)
+            )?;
+            for mark in marks {
+                execute!(
+                    stdout(),
+                    Print(" "),
+                    Print(mark.player.to_char()),
+                    Print(subscript(mark.turn))
+                )?;
+            }
+        }
+        execute!(stdout(), ResetColor)?;
+        Ok(())
+    }
+}
+
+/// A game of quantum tic-tac-toe between the player, who plays crosses and
+/// moves first, and the AI.
+#[derive(Debug, PartialEq)]
+pub struct QuantumTicTacToe {
+    pub cursor: Coordinates,
+    pub grid: QuantumGrid,
+    pub ai_algo: AI,
+    pub board: QuantumBoard,
+    /// The first square of the spooky mark the player is placing.
+    pub selected: Option<Coordinates>,
+    /// The most time the AI may think about a single move.
+    pub ai_time: Duration,
+    rng: StdRng,
+}
+
+impl QuantumTicTacToe {
+    pub fn from_seed(ai_algo: AI, seed: u64) -> Self {
+        Self {
+            cursor: Coordinates { x: 1, y: 1, z: 0 },
+            grid: QuantumGrid,
+            ai_algo,
+            board: QuantumBoard::default(),
+            selected: None,
+            ai_time: DEFAULT_THINK_TIME,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The game loop reads player input and performs actions based on this input.
+    pub fn game_loop(&mut self) -> crossterm::Result<()> {
+        Grid::cleanup()?;
+        self.draw()?;
+        loop {
+            match read_input_event(None)? {
+                InputEvent::Direction(direction) => {
+                    let mut position = self.cursor + direction.get_relative_coords();
+                    position.x = position.x.clamp(0, SIDE - 1);
+                    position.y = position.y.clamp(0, SIDE - 1);
+                    // The board has no layers to move between.
+                    position.z = 0;
+                    self.set_cursor(position)?;
+                }
+                InputEvent::Quit => break,
+                InputEvent::Tick | InputEvent::ToggleSymbol | InputEvent::Prompt => {}
+                InputEvent::Mark => {
+                    let next = match self.player_move() {
+                        Some(next) => next,
+                        None => {
+                            self.draw()?;
+                            continue;
+                        }
+                    };
+                    // Let's ignore moves into squares which have collapsed already.
+                    if self.board.mark(next).is_err() {
+                        continue;
+                    }
+                    self.draw()?;
+                    // Once the player closes a cycle, the AI first collapses it
+                    // and then makes its own move.
+                    while self.board.outcome().is_none() && self.board.to_move == Player::Zero {
+                        let deadline = Instant::now() + self.ai_time;
+                        let ai_move =
+                            self.ai_algo
+                                .get_quantum_move(&self.board, &mut self.rng, deadline);
+                        self.board.mark(ai_move)?;
+                        self.draw()?;
+                    }
+                    if let Some(outcome) = self.board.outcome() {
+                        self.announce(outcome)?;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The move the player makes by pressing Enter on the square under the
+    /// cursor, if it completes one. A spooky mark takes two presses: the first
+    /// selects a square, and pressing it again deselects it.
+    fn player_move(&mut self) -> Option<QuantumMove> {
+        if self.board.pending.is_some() {
+            return Some(QuantumMove::Collapse(self.cursor));
+        }
+        if self.board.open_squares().len() == 1 {
+            return Some(QuantumMove::Place(self.cursor));
+        }
+        match self.selected.take() {
+            Some(selected) if selected == self.cursor => None,
+            Some(selected) => Some(QuantumMove::Spooky(selected, self.cursor)),
+            None => {
+                if self.board.classical.get(&self.cursor).is_none() {
+                    self.selected = Some(self.cursor);
+                }
+                None
+            }
+        }
+    }
+
+    fn draw(&self) -> crossterm::Result<()> {
+        let highlighted = match (self.board.pending, self.selected) {
+            (Some(mark), _) => mark.squares.to_vec(),
+            (None, Some(selected)) => vec![selected],
+            (None, None) => Vec::new(),
+        };
+        self.grid.draw(&self.board, &highlighted)?;
+        if let Some(mark) = self.board.pending {
+            let msg = format!(
+                "Pick the square {}{} collapses into",
+                mark.player.to_char(),
+                subscript(mark.turn)
+            );
+            print_message(QuantumGrid::message_row(), &msg)?;
+        } else {
+            print_message(QuantumGrid::message_row(), "")?;
+        }
+        Self::move_cursor_to_grid(&self.cursor)
+    }
+
+    fn set_cursor(&mut self, position: Coordinates) -> crossterm::Result<()> {
+        self.cursor = position;
+        Self::move_cursor_to_grid(&position)
+    }
+
+    fn move_cursor_to_grid(position: &Coordinates) -> crossterm::Result<()> {
+        let screen_coords = QuantumGrid::grid_coords_to_screen_coords(position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+        )?;
+        Ok(())
+    }
+
+    fn announce(&self, outcome: Outcome) -> crossterm::Result<()> {
+        let msg = match outcome {
+            Outcome::Win(Player::Cross) => "You've won the game!",
+            Outcome::Win(_) => "AI won the game!",
+            Outcome::Draw => "The game was a draw!",
+        };
+        print_message(QuantumGrid::message_row(), msg)
+    }
+}
@@ -0,0 +1,216 @@
+use super::ai::AI;
+use super::stats::{data_dir, GameResult};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Rating of a player who has not finished a game yet.
+pub const STARTING_RATING: f64 = 1200.0;
+
+/// How far a single game can move a rating.
+pub const K_FACTOR: f64 = 32.0;
+
+/// Prefix of the names AIs are rated under, which keeps them apart from the
+/// player profiles since those cannot contain a colon.
+const AI_PREFIX: &str = "ai:";
+
+/// Elo rating of a player profile or an AI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    /// Number of rated games played.
+    pub games: u32,
+}
+
+/// Elo ratings of every player profile and AI which has played a rated game.
+///
+/// Ratings are stored as plain text with a line for every profile or AI,
+/// giving its name, rating and number of games, where AIs are named after
+/// what they are picked by on the command line and, for those which think
+/// about their moves, the milliseconds they may think for:
+///
+/// ```text
+/// rating default 1216.0 1
+/// rating ai:random 984.0 1
+/// rating ai:minimax:1000 1816.0 1
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ratings {
+    pub players: BTreeMap<String, Rating>,
+}
+
+/// The file the ratings are kept in, next to the statistics, if there is a
+/// data directory.
+pub fn ratings_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("ratings"))
+}
+
+/// The name an AI is rated under when it may think for the given time on a
+/// move, since the longer it thinks the stronger it plays. The random AI
+/// does not think and is rated the same whatever the time.
+pub fn ai_name(ai: AI, think_time: Duration) -> String {
+    match ai {
+        AI::Random => format!("{}{}", AI_PREFIX, ai),
+        _ => format!("{}{}:{}", AI_PREFIX, ai, think_time.as_millis()),
+    }
+}
+
+/// The AI a rated name stands for, along with its think time if it has one,
+/// or `None` for the names of player profiles.
+fn parse_ai_name(name: &str) -> Option<(AI, Option<&str>)> {
+    let name = name.strip_prefix(AI_PREFIX)?;
+    let (ai, think_time) = match name.split_once(':') {
+        Some((ai, think_time)) => (ai, Some(think_time)),
+        None => (name, None),
+    };
+    Some((ai.parse().ok()?, think_time))
+}
+
+/// The rating an AI starts with, so that the stronger ones start higher and
+/// a new player is matched with a weak one.
+pub fn starting_rating(ai: AI) -> f64 {
+    match ai {
+        AI::Random => 1000.0,
+        AI::MonteCarlo => 1400.0,
+        AI::MiniMax => 1800.0,
+    }
+}
+
+/// The expected score of a player against an opponent: 1 for a sure win, 0
+/// for a sure loss.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+impl Ratings {
+    /// Reads the ratings from the given file; a missing file holds none.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the ratings to the given file, creating its directory if need
+    /// be.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    /// The rating of the named profile or AI, which starts out at
+    /// `STARTING_RATING` for profiles and `starting_rating` for AIs.
+    pub fn rating(&self, name: &str) -> Rating {
+        self.players.get(name).copied().unwrap_or_else(|| {
+            let elo = parse_ai_name(name).map_or(STARTING_RATING, |(ai, _)| starting_rating(ai));
+            Rating { elo, games: 0 }
+        })
+    }
+
+    /// Rates a game between the player and the opponent, with the result as
+    /// seen by the player.
+    pub fn update(&mut self, player: &str, opponent: &str, result: GameResult) {
+        let score = match result {
+            GameResult::Win => 1.0,
+            GameResult::Draw => 0.5,
+            GameResult::Loss => 0.0,
+        };
+        let mut rating = self.rating(player);
+        let mut opponent_rating = self.rating(opponent);
+        let expected = expected_score(rating.elo, opponent_rating.elo);
+        rating.elo += K_FACTOR * (score - expected);
+        rating.games += 1;
+        opponent_rating.elo -= K_FACTOR * (score - expected);
+        opponent_rating.games += 1;
+        self.players.insert(player.to_string(), rating);
+        self.players.insert(opponent.to_string(), opponent_rating);
+    }
+
+    /// The AI among the given ones, thinking for the given time on a move,
+    /// whose rating is closest to the player's, the weaker one on a tie.
+    pub fn closest_ai(&self, player: &str, ais: &[AI], think_time: Duration) -> Option<AI> {
+        let player = self.rating(player).elo;
+        let elo = |ai: &AI| self.rating(&ai_name(*ai, think_time)).elo;
+        let distance = |ai: &AI| (elo(ai) - player).abs();
+        ais.iter().copied().min_by(|a, b| {
+            distance(a)
+                .total_cmp(&distance(b))
+                .then(elo(a).total_cmp(&elo(b)))
+        })
+    }
+
+    /// Every profile and AI, best rated first, as lines of text.
+    pub fn leaderboard(&self) -> Vec<String> {
+        let mut players: Vec<(&String, &Rating)> = self.players.iter().collect();
+        players.sort_by(|(_, a), (_, b)| b.elo.total_cmp(&a.elo));
+        players
+            .iter()
+            .enumerate()
+            .map(|(i, (name, rating))| {
+                let name = match parse_ai_name(name) {
+                    Some((ai, Some(think_time))) => format!("{} (AI, {}ms)", ai, think_time),
+                    Some((ai, None)) => format!("{} (AI)", ai),
+                    None => name.to_string(),
+                };
+                format!(
+                    "{:>3}. {:<20} {:>5.0} in {} game{}",
+                    i + 1,
+                    name,
+                    rating.elo,
+                    rating.games,
+                    if rating.games == 1 { "" } else { "s" }
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Ratings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, rating) in &self.players {
+            writeln!(f, "rating {} {:.1} {}", name, rating.elo, rating.games)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Ratings {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let mut players = BTreeMap::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["rating", name, elo, games] => {
+                    let rating = Rating {
+                        elo: elo
+                            .parse()
+                            .ok()
+                            .filter(|elo: &f64| elo.is_finite())
+                            .ok_or_else(|| invalid_ratings(line))?,
+                        games: games.parse().map_err(|_| invalid_ratings(line))?,
+                    };
+                    players.insert(name.to_string(), rating);
+                }
+                _ => return Err(invalid_ratings(line)),
+            }
+        }
+        Ok(Self { players })
+    }
+}
+
+fn invalid_ratings(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid ratings: {}", line),
+    )
+}
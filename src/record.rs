@@ -0,0 +1,343 @@
+use super::board::Board;
+use super::cli::parse_board_config;
+use super::rules::Variant;
+use super::swap::Swap;
+use super::{Coordinates, Move, Player};
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// A saved game: the board and rules it was played with, the seed the AI drew
+/// its random numbers from and every move in the order it was played. Replaying the
+/// moves with the same seed reproduces the game exactly.
+///
+/// Records are stored as plain text, one `key value` pair per line:
+///
+/// ```text
+/// seed 42
+/// board 3x3x3
+/// rules classic
+/// move X b2
+/// move 0 a3
+/// ```
+///
+/// Positions are written in the algebraic notation of `Coordinates`, though
+/// records giving them as numbers from 0, as in `move X 1 1`, can be read
+/// too. Games on boards of several layers add a `layers` line and give the
+/// layer of every move after a slash, as in `move X b2/3`, or as a third
+/// number. Games played with gravity add a `gravity on` line, and games on a
+/// torus a `torus on` line. Every blocked position of the board gets a line
+/// such as `blocked b2`. Moves which slide a piece end with where it came
+/// from, as in `move X b2 from a1`.
+///
+/// Games played with a swap rule add a line such as `swap pie`, followed by
+/// `sides swapped` if the player who got to choose took the opener's side. The
+/// symbols of every mark on the board were turned around right after the
+/// opening stones then, and the moves after it are recorded as played.
+///
+/// Games started from a position set up in the editor give every mark on
+/// the board before the first move with a line such as `setup X b2`, and
+/// add `first 0` if zeros moved first.
+///
+/// Games studied in analysis mode keep the variations tried along the way,
+/// each on a line of its own with every move from the start of the game, as
+/// in `variation X b2, 0 c3, X a1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    /// Number of layers of the board; flat boards have one.
+    pub depth: u16,
+    pub win_length: u16,
+    pub gravity: bool,
+    pub torus: bool,
+    /// Positions of the board which can never be marked.
+    pub blocked: Vec<Coordinates>,
+    /// Marks on the board before the first move, for games started from a
+    /// position set up in the editor.
+    pub setup: Vec<Move>,
+    /// The player who moved first.
+    pub first: Player,
+    /// The name of the rules, as written by their `Display`. Only records
+    /// of the built-in `Variant`s can be read back, since the rules of other
+    /// crates can't be told from their name.
+    pub rules: String,
+    /// The swap rule the game opened with, if any.
+    pub swap: Option<Swap>,
+    /// Whether the players swapped sides after the opening.
+    pub swapped: bool,
+    /// Every move in the order it was played. Players take turns starting
+    /// with crosses, so unless the rules let them place either symbol, the
+    /// symbol placed is also the player who moved.
+    pub moves: Vec<Move>,
+    /// Other lines of play tried in analysis, each from the start of the
+    /// game.
+    pub variations: Vec<Vec<Move>>,
+}
+
+impl GameRecord {
+    pub fn from(board: &Board, seed: u64) -> Self {
+        Self {
+            seed,
+            width: board.width,
+            height: board.height,
+            depth: board.depth,
+            win_length: board.win_length,
+            gravity: board.gravity,
+            torus: board.torus,
+            blocked: board.blocked_positions(),
+            setup: Vec::new(),
+            first: Player::Cross,
+            rules: Variant::default().to_string(),
+            swap: None,
+            swapped: false,
+            moves: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+
+    /// A record of a game starting from the position set up on the board,
+    /// with the given player to move first.
+    pub fn from_position(board: &Board, first: Player, seed: u64) -> Self {
+        let mut setup: Vec<Move> = board
+            .marked_positions()
+            .iter()
+            .map(|(position, player)| Move::place(*position, *player))
+            .collect();
+        // From the bottom row up, so that marks can be set up under gravity.
+        setup.sort_by_key(|next| (next.position.z, -next.position.y, next.position.x));
+        Self {
+            setup,
+            first,
+            ..Self::from(board, seed)
+        }
+    }
+
+    /// The board of the recorded game before the first move: empty unless a
+    /// position was set up on it.
+    pub fn board(&self) -> Board {
+        let mut board = Board::layered(self.width, self.height, self.depth, self.win_length);
+        board.gravity = self.gravity;
+        board.torus = self.torus;
+        for position in &self.blocked {
+            board
+                .block(*position)
+                .expect("blocked positions lie on the board");
+        }
+        for next in &self.setup {
+            board
+                .mark(next.position, next.symbol)
+                .expect("set-up marks lie on free positions of the board");
+        }
+        board
+    }
+
+    fn write_move(&self, f: &mut fmt::Formatter, next: &Move) -> fmt::Result {
+        write!(f, "{} {}", next.symbol.to_char(), next.position)?;
+        if let Some(from) = next.from {
+            write!(f, " from {}", from)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(
+            f,
+            "board {}x{}x{}",
+            self.width, self.height, self.win_length
+        )?;
+        if self.depth > 1 {
+            writeln!(f, "layers {}", self.depth)?;
+        }
+        if self.gravity {
+            writeln!(f, "gravity on")?;
+        }
+        if self.torus {
+            writeln!(f, "torus on")?;
+        }
+        for position in &self.blocked {
+            writeln!(f, "blocked {}", position)?;
+        }
+        for next in &self.setup {
+            write!(f, "setup ")?;
+            self.write_move(f, next)?;
+            writeln!(f)?;
+        }
+        if self.first != Player::Cross {
+            writeln!(f, "first {}", self.first.to_char())?;
+        }
+        writeln!(f, "rules {}", self.rules)?;
+        if let Some(swap) = self.swap {
+            writeln!(f, "swap {}", swap)?;
+        }
+        if self.swapped {
+            writeln!(f, "sides swapped")?;
+        }
+        for next in &self.moves {
+            write!(f, "move ")?;
+            self.write_move(f, next)?;
+            writeln!(f)?;
+        }
+        for variation in &self.variations {
+            write!(f, "variation ")?;
+            for (i, next) in variation.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                self.write_move(f, next)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let mut seed = None;
+        let mut board = None;
+        let mut depth = 1;
+        let mut gravity = false;
+        let mut torus = false;
+        let mut blocked = Vec::new();
+        let mut setup = Vec::new();
+        let mut first = Player::Cross;
+        let mut rules = Variant::default().to_string();
+        let mut swap = None;
+        let mut swapped = false;
+        let mut moves = Vec::new();
+        let mut variations = Vec::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["seed", value] => seed = Some(parse_field(line, value)?),
+                ["board", value] => {
+                    board = Some(parse_board_config(value)?);
+                }
+                ["layers", value] => depth = parse_field(line, value)?,
+                ["gravity", "on"] => gravity = true,
+                ["gravity", "off"] => gravity = false,
+                ["torus", "on"] => torus = true,
+                ["torus", "off"] => torus = false,
+                ["blocked", ref fields @ ..] => blocked.push(parse_position(line, fields)?),
+                ["setup", player, ref fields @ ..] => setup.push(parse_move(line, player, fields)?),
+                ["first", player] => first = parse_player(line, player)?,
+                ["rules", value] => {
+                    parse_field::<Variant>(line, value)?;
+                    rules = value.to_string();
+                }
+                ["swap", value] => swap = Some(parse_field(line, value)?),
+                ["sides", "swapped"] => swapped = true,
+                ["move", player, ref fields @ ..] => moves.push(parse_move(line, player, fields)?),
+                ["variation", ..] => {
+                    let variation = line["variation".len()..]
+                        .split(',')
+                        .map(
+                            |next| match next.split_whitespace().collect::<Vec<_>>()[..] {
+                                [player, ref fields @ ..] => parse_move(line, player, fields),
+                                [] => Err(invalid_record(line)),
+                            },
+                        )
+                        .collect::<io::Result<Vec<Move>>>()?;
+                    variations.push(variation);
+                }
+                _ => return Err(invalid_record(line)),
+            }
+        }
+        let seed = seed.ok_or_else(|| invalid_record("missing seed"))?;
+        if swapped && swap.is_none() {
+            return Err(invalid_record("sides swapped without a swap rule"));
+        }
+        let (width, height, win_length) = board.ok_or_else(|| invalid_record("missing board"))?;
+        // `board` relies on the blocked positions lying on the board, and the
+        // set-up marks on free positions.
+        let mut board = Board::layered(width, height, depth, win_length);
+        for position in &blocked {
+            board
+                .block(*position)
+                .map_err(|_| invalid_record("blocked position off the board"))?;
+        }
+        board.gravity = gravity;
+        for next in &setup {
+            board
+                .mark(next.position, next.symbol)
+                .map_err(|_| invalid_record("set-up mark off the free positions"))?;
+        }
+        Ok(Self {
+            seed,
+            width,
+            height,
+            depth,
+            win_length,
+            gravity,
+            torus,
+            blocked,
+            setup,
+            first,
+            rules,
+            swap,
+            swapped,
+            moves,
+            variations,
+        })
+    }
+}
+
+fn parse_move(line: &str, player: &str, fields: &[&str]) -> io::Result<Move> {
+    let symbol = parse_player(line, player)?;
+    let (position, from) = match fields.iter().position(|field| *field == "from") {
+        Some(index) => (&fields[..index], Some(&fields[index + 1..])),
+        None => (fields, None),
+    };
+    Ok(Move {
+        position: parse_position(line, position)?,
+        symbol,
+        from: from.map(|from| parse_position(line, from)).transpose()?,
+    })
+}
+
+/// Parses a player written as their symbol, and nothing more.
+fn parse_player(line: &str, field: &str) -> io::Result<Player> {
+    let mut chars = field.chars();
+    match (chars.next().and_then(Player::from_char), chars.next()) {
+        (Some(player), None) => Ok(player),
+        _ => Err(invalid_record(line)),
+    }
+}
+
+/// Parses a position given in algebraic notation, or as `x y`, or `x y z` on
+/// boards of several layers.
+fn parse_position(line: &str, fields: &[&str]) -> io::Result<Coordinates> {
+    match *fields {
+        [position] => parse_field(line, position),
+        [x, y] => Ok(Coordinates {
+            x: parse_field(line, x)?,
+            y: parse_field(line, y)?,
+            z: 0,
+        }),
+        [x, y, z] => Ok(Coordinates {
+            x: parse_field(line, x)?,
+            y: parse_field(line, y)?,
+            z: parse_field(line, z)?,
+        }),
+        _ => Err(invalid_record(line)),
+    }
+}
+
+fn parse_field<T: FromStr>(line: &str, value: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_record(line))
+}
+
+fn invalid_record(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid game record: {}", line),
+    )
+}
@@ -0,0 +1,229 @@
+use super::board::Board;
+use super::game::print_message;
+use super::grid::Grid;
+use super::record::GameRecord;
+use super::rules::{Rules, Variant};
+use super::{Move, Player};
+
+use crossterm::event::{poll, read, Event, KeyCode};
+use crossterm::{cursor, execute, terminal};
+use std::io;
+use std::io::stdout;
+use std::time::Duration;
+
+/// How long each move is shown while a replay plays by itself, unless told
+/// otherwise.
+pub const DEFAULT_SPEED: Duration = Duration::from_millis(1000);
+
+/// Steps through a saved game on the grid, one move at a time.
+pub struct Replay {
+    pub record: GameRecord,
+    pub grid: Grid,
+    /// How long each move is shown while the replay plays by itself.
+    pub speed: Duration,
+    /// The board after every number of moves, from the empty board to the
+    /// end of the game.
+    positions: Vec<Board>,
+    /// The player who made every move.
+    movers: Vec<Player>,
+    /// Number of moves played on the board shown.
+    current: usize,
+    playing: bool,
+}
+
+/// The board after every number of moves of the recorded game, from the empty
+/// board to the end of the game. Sides are swapped right after the opening
+/// stones if the record says so.
+pub fn positions(record: &GameRecord) -> io::Result<Vec<Board>> {
+    play_line(record, &record.moves)
+}
+
+/// Like `positions`, but for the given moves played on the board and under
+/// the rules of the recorded game, such as one of its variations.
+pub fn play_line(record: &GameRecord, moves: &[Move]) -> io::Result<Vec<Board>> {
+    let rules: Variant = record.rules.parse()?;
+    let opening = record
+        .swap
+        .map_or(0, |swap| swap.opening(Player::Cross).len());
+    let mut board = record.board();
+    let mut positions = vec![board.clone()];
+    for (i, next) in moves.iter().enumerate() {
+        rules.apply(&mut board, next)?;
+        if record.swapped && i + 1 == opening {
+            board.swap_sides();
+        }
+        positions.push(board.clone());
+    }
+    Ok(positions)
+}
+
+/// The player who made every move of the recorded game; see `turns`.
+pub fn movers(record: &GameRecord) -> io::Result<Vec<Player>> {
+    let mut movers = turns(record, &record.moves)?;
+    movers.pop();
+    Ok(movers)
+}
+
+/// The player who made each of the given moves of the recorded game, such as
+/// one of its variations, followed by the player to move after them. Players
+/// take turns from the one who moved first, whichever symbol they placed;
+/// but under a swap rule, where the opener places stones of both sides and
+/// the players may trade sides after, each move is put down to the side it
+/// was placed for, and the opener moves again once the other player has
+/// taken over their stones.
+pub fn turns(record: &GameRecord, moves: &[Move]) -> io::Result<Vec<Player>> {
+    let rules: Variant = record.rules.parse()?;
+    let positions = play_line(record, moves)?;
+    let swap = match record.swap {
+        Some(swap) => swap,
+        None => {
+            let mut player = record.first;
+            let mut turns = Vec::with_capacity(moves.len() + 1);
+            for position in &positions[1..] {
+                turns.push(player);
+                player = rules.next_player(position, player);
+            }
+            turns.push(player);
+            return Ok(turns);
+        }
+    };
+    let opener = moves.first().map_or(record.first, |next| next.symbol);
+    let opening = swap.opening(opener);
+    let mut turns: Vec<Player> = moves.iter().map(|next| next.symbol).collect();
+    let next = match (opening.get(moves.len()), moves.last()) {
+        (Some(symbol), _) => *symbol,
+        (None, _) if record.swapped && moves.len() == opening.len() => opener,
+        (None, Some(last)) => rules.next_player(positions.last().unwrap(), last.symbol),
+        (None, None) => unreachable!("every opening places a stone"),
+    };
+    turns.push(next);
+    Ok(turns)
+}
+
+impl Replay {
+    pub fn new(record: GameRecord) -> io::Result<Self> {
+        let positions = positions(&record)?;
+        let movers = movers(&record)?;
+        let grid = Grid {
+            width: record.width,
+            height: record.height,
+            layers: record.depth,
+        };
+        Ok(Self {
+            record,
+            grid,
+            speed: DEFAULT_SPEED,
+            positions,
+            movers,
+            current: 0,
+            playing: false,
+        })
+    }
+
+    /// Number of moves played on the board shown.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Shows the board after the given number of moves, or after the last
+    /// one if there are fewer.
+    pub fn go_to(&mut self, moves: usize) {
+        self.current = moves.min(self.record.moves.len());
+    }
+
+    pub fn forward(&mut self) {
+        self.go_to(self.current + 1);
+    }
+
+    pub fn back(&mut self) {
+        self.go_to(self.current.saturating_sub(1));
+    }
+
+    /// The board shown.
+    pub fn board(&self) -> &Board {
+        &self.positions[self.current]
+    }
+
+    /// What the status line says about the board shown, such as
+    /// `Move 3 of 9: X at b2`, or `Move 3 of 9: X plays 0 at b2` when the
+    /// player placed a symbol other than their own.
+    pub fn status(&self) -> String {
+        let total = self.record.moves.len();
+        if self.current == 0 {
+            return format!("Start of the game, {} moves", total);
+        }
+        let last = &self.record.moves[self.current - 1];
+        let mover = self.movers[self.current - 1];
+        let mut status = format!("Move {} of {}: {}", self.current, total, mover.to_char());
+        if last.symbol != mover {
+            status += &format!(" plays {}", last.symbol.to_char());
+        }
+        status += &format!(" at {}", last.position);
+        if let Some(from) = last.from {
+            status += &format!(" from {}", from);
+        }
+        status
+    }
+
+    /// Shows the replay until Esc is pressed. The arrow keys step through the
+    /// moves, Home and End jump to the start and the end, and Space plays the
+    /// moves by themselves or pauses.
+    pub fn show(&mut self) -> crossterm::Result<()> {
+        self.draw()?;
+        loop {
+            let at_end = self.current == self.record.moves.len();
+            if self.playing && at_end {
+                self.playing = false;
+                self.draw()?;
+            }
+            if self.playing && !poll(self.speed)? {
+                self.forward();
+                self.draw()?;
+                continue;
+            }
+            if let Event::Key(k) = read()? {
+                match k.code {
+                    KeyCode::Right | KeyCode::Char('d') => self.forward(),
+                    KeyCode::Left | KeyCode::Char('a') => self.back(),
+                    KeyCode::Home => self.go_to(0),
+                    KeyCode::End => self.go_to(self.record.moves.len()),
+                    KeyCode::Char(' ') => {
+                        if at_end {
+                            self.go_to(0);
+                        }
+                        self.playing = !self.playing;
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => break,
+                    _ => continue,
+                }
+                self.draw()?;
+            }
+        }
+        terminal::disable_raw_mode()?;
+        execute!(stdout(), cursor::MoveTo(0, self.grid.bottom() + 3))?;
+        println!();
+        Ok(())
+    }
+
+    /// Redraws the grid with the board shown, the latest move highlighted.
+    fn draw(&mut self) -> crossterm::Result<()> {
+        let board = &self.positions[self.current];
+        self.grid.draw()?.draw_position(board)?;
+        if self.current > 0 {
+            let last = self.record.moves[self.current - 1].position;
+            if let Some(player) = board.get(&last) {
+                self.grid.highlight_at(last, player.to_char())?;
+            }
+        }
+        print_message(self.grid.bottom() + 1, &self.status())?;
+        let play = if self.playing { "pause" } else { "play" };
+        print_message(
+            self.grid.bottom() + 2,
+            &format!(
+                "Left/Right: step  Home/End: start/end  Space: {}  Esc: quit",
+                play
+            ),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,489 @@
+use super::board::{Board, Undo};
+use super::{Coordinates, Move, Outcome, Player};
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// The rules of a game played on a `Board`: which moves a player may make,
+/// how a move changes the board, how the game ends and who moves next. Every
+/// method has a default which plays the classic game, where players take turns
+/// placing their own symbol and the first to complete a line wins, so a
+/// variant only overrides what it changes. The game loop and the AIs only go
+/// through this trait, so other crates can supply their own variants.
+///
+/// The rules' `Display` names them in game records.
+pub trait Rules: fmt::Debug + fmt::Display {
+    /// Every move the player may make on the board.
+    fn legal_moves(&self, board: &Board, player: Player) -> Vec<Move> {
+        placements(board, &[player])
+    }
+
+    /// Plays a legal move on the board, returning what it takes to take it
+    /// back.
+    fn apply(&self, board: &mut Board, next: &Move) -> io::Result<Undo> {
+        board.play(next)
+    }
+
+    /// Takes back the latest move played with `apply`. Rules which change
+    /// more in `apply` than `Board::play` does take that back here too.
+    fn undo(&self, board: &mut Board, undo: &Undo) {
+        board.undo(undo)
+    }
+
+    /// How the game ended after the player marked the given position, or
+    /// `None` if it goes on.
+    fn outcome(&self, board: &Board, position: &Coordinates, player: Player) -> Option<Outcome> {
+        let symbol = board.get(position)?;
+        if board.completes_line(position, symbol) {
+            Some(Outcome::Win(player))
+        } else if board.is_full() {
+            Some(Outcome::Draw)
+        } else {
+            None
+        }
+    }
+
+    /// The player who moves after the given player has moved.
+    fn next_player(&self, _board: &Board, player: Player) -> Player {
+        player.opponent()
+    }
+
+    /// The most moves which can still be played before the game ends, or
+    /// `None` if it may go on forever.
+    fn max_moves(&self, board: &Board) -> Option<usize> {
+        Some(board.empty_positions().len())
+    }
+
+    /// Whether players may place either symbol rather than only their own.
+    fn allows_any_symbol(&self) -> bool {
+        false
+    }
+
+    /// The mark which the player's next move takes off the board, whichever
+    /// move it is.
+    fn vanishing_mark(&self, _board: &Board, _player: Player) -> Option<Coordinates> {
+        None
+    }
+
+    /// The most marks a player can have on the board at once, or `None` if
+    /// there is no limit.
+    fn max_marks(&self) -> Option<usize> {
+        None
+    }
+
+    /// Heuristic score of an undecided position for the player to move, used
+    /// by search-based AIs: every line of `win_length` positions which only one
+    /// player has marked counts in that player's favour, more so the more marks
+    /// it holds.
+    fn evaluate(&self, board: &Board, player: Player) -> i32 {
+        line_balance(board, player)
+    }
+}
+
+/// The variants of the game which can be picked from the command line. Each
+/// decides which symbols a player may place and what completing a line on the
+/// board means for the game.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Variant {
+    /// The player who completes a line wins.
+    #[default]
+    Classic,
+    /// The player who completes a line loses.
+    Misere,
+    /// Players may place either symbol, and whoever completes a line of
+    /// either symbol wins.
+    Wild,
+    /// Players may place either symbol. Order, who plays crosses and moves
+    /// first, wins as soon as a line of either symbol is completed, while
+    /// Chaos wins by filling the board without one.
+    OrderAndChaos,
+    /// Each player has only so many pieces, as in three men's morris. Once a
+    /// player has placed them all, they move one of their pieces to an empty
+    /// position on every turn instead. Completing a line wins.
+    Sliding { pieces: u16, movement: Movement },
+    /// Only each player's latest marks stay on the board: placing another
+    /// mark removes the player's oldest one, so the board never fills up.
+    /// Completing a line wins.
+    Vanishing { marks: u16 },
+    /// More than two players take turns in the order of `Player::ALL`, each
+    /// placing their own symbol. Completing a line wins.
+    Multiplayer { players: u16 },
+}
+
+/// Where a piece may slide to under `Variant::Sliding`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+    /// To one of the up to eight positions next to it, diagonals included.
+    Adjacent,
+    /// To any empty position.
+    Anywhere,
+}
+
+/// Number of pieces each player gets under `Variant::Sliding` unless told
+/// otherwise.
+pub const DEFAULT_PIECES: u16 = 3;
+
+/// Number of marks each player keeps under `Variant::Vanishing` unless told
+/// otherwise.
+pub const DEFAULT_MARKS: u16 = 3;
+
+/// Number of players under `Variant::Multiplayer` unless told otherwise.
+pub const DEFAULT_PLAYERS: u16 = 3;
+
+impl Rules for Variant {
+    /// Lines are made of the symbol on the position, which is not necessarily
+    /// the player's own.
+    fn outcome(&self, board: &Board, position: &Coordinates, player: Player) -> Option<Outcome> {
+        let symbol = board.get(position)?;
+        if board.completes_line(position, symbol) {
+            Some(self.outcome_of_line(player))
+        } else if board.is_full() {
+            match self {
+                Self::OrderAndChaos => Some(Outcome::Win(Player::Zero)),
+                _ => Some(Outcome::Draw),
+            }
+        } else if let Self::Sliding { .. } = self {
+            // The game is drawn if the opponent's pieces are all stuck.
+            if self.legal_moves(board, player.opponent()).is_empty() {
+                Some(Outcome::Draw)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Pieces move around under sliding and vanishing rules, so those games
+    /// may go on forever.
+    fn max_moves(&self, board: &Board) -> Option<usize> {
+        match self {
+            Self::Sliding { .. } | Self::Vanishing { .. } => None,
+            _ => Some(board.empty_positions().len()),
+        }
+    }
+
+    fn allows_any_symbol(&self) -> bool {
+        matches!(self, Self::Wild | Self::OrderAndChaos)
+    }
+
+    /// Sliding players run out of pieces, and vanishing ones of marks.
+    fn max_marks(&self) -> Option<usize> {
+        match self {
+            Self::Sliding { pieces, .. } => Some(usize::from(*pieces)),
+            Self::Vanishing { marks } => Some(usize::from(*marks)),
+            _ => None,
+        }
+    }
+
+    fn next_player(&self, _board: &Board, player: Player) -> Player {
+        match self {
+            Self::Multiplayer { players } => player.next(usize::from(*players)),
+            _ => player.opponent(),
+        }
+    }
+
+    fn legal_moves(&self, board: &Board, player: Player) -> Vec<Move> {
+        match self {
+            Self::Sliding { pieces, movement } => {
+                let own = board.marks_of(player);
+                if own.len() >= usize::from(*pieces) {
+                    return Self::slides(board, own, player, *movement);
+                }
+            }
+            Self::Vanishing { .. } => {
+                if let Some(oldest) = self.vanishing_mark(board, player) {
+                    return board
+                        .legal_moves()
+                        .into_iter()
+                        .map(|position| Move {
+                            position,
+                            symbol: player,
+                            from: Some(oldest),
+                        })
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+        if self.allows_any_symbol() {
+            placements(board, &[Player::Cross, Player::Zero])
+        } else {
+            placements(board, &[player])
+        }
+    }
+
+    /// Under vanishing rules, the oldest mark of a player who has run out of
+    /// marks.
+    fn vanishing_mark(&self, board: &Board, player: Player) -> Option<Coordinates> {
+        match self {
+            Self::Vanishing { marks } => {
+                let own = board.marks_of(player);
+                if own.len() >= usize::from(*marks) {
+                    Some(own[0])
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Under misère rules lines count against the player instead. Under Order
+    /// and Chaos every line holding a single symbol counts in Order's favour,
+    /// while wild games, where any line can be finished by either player, are
+    /// left to the search.
+    fn evaluate(&self, board: &Board, player: Player) -> i32 {
+        match self {
+            Self::Wild => 0,
+            Self::Misere => -line_balance(board, player),
+            Self::OrderAndChaos => {
+                let mut score = 0;
+                for line in board.lines() {
+                    let count = |symbol| {
+                        line.iter()
+                            .filter(|position| board.get(position) == Some(&symbol))
+                            .count() as i32
+                    };
+                    let (crosses, zeros) = (count(Player::Cross), count(Player::Zero));
+                    if crosses > 0 && zeros > 0 {
+                        continue;
+                    }
+                    score += (crosses + zeros) * (crosses + zeros);
+                }
+                if player == Player::Zero {
+                    -score
+                } else {
+                    score
+                }
+            }
+            _ => line_balance(board, player),
+        }
+    }
+}
+
+impl Variant {
+    /// What it means for the game that the player has completed a line.
+    pub fn outcome_of_line(&self, player: Player) -> Outcome {
+        match self {
+            Self::Classic
+            | Self::Wild
+            | Self::Sliding { .. }
+            | Self::Vanishing { .. }
+            | Self::Multiplayer { .. } => Outcome::Win(player),
+            Self::Misere => Outcome::Win(player.opponent()),
+            Self::OrderAndChaos => Outcome::Win(Player::Cross),
+        }
+    }
+
+    /// Every way to slide one of the player's pieces.
+    fn slides(
+        board: &Board,
+        pieces: Vec<Coordinates>,
+        player: Player,
+        movement: Movement,
+    ) -> Vec<Move> {
+        let empty = board.empty_positions();
+        let mut moves = Vec::new();
+        for from in pieces {
+            for position in &empty {
+                let distance = (position.x - from.x)
+                    .abs()
+                    .max((position.y - from.y).abs())
+                    .max((position.z - from.z).abs());
+                if movement == Movement::Anywhere || distance == 1 {
+                    moves.push(Move {
+                        position: *position,
+                        symbol: player,
+                        from: Some(from),
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    /// Width, height and win length of the board the rules are played on
+    /// unless another board is asked for.
+    pub fn default_board(&self) -> (u16, u16, u16) {
+        match self {
+            Self::OrderAndChaos => (6, 6, 5),
+            Self::Multiplayer { .. } => (6, 6, 4),
+            _ => (3, 3, 3),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Classic => "classic",
+            Self::Misere => "misere",
+            Self::Wild => "wild",
+            Self::OrderAndChaos => "order-and-chaos",
+            Self::Sliding { pieces, movement } => {
+                let movement = match movement {
+                    Movement::Adjacent => "adjacent",
+                    Movement::Anywhere => "anywhere",
+                };
+                return write!(f, "sliding:{}:{}", pieces, movement);
+            }
+            Self::Vanishing { marks } => return write!(f, "vanishing:{}", marks),
+            Self::Multiplayer { players } => return write!(f, "multiplayer:{}", players),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Variant {
+    type Err = io::Error;
+
+    /// Parses the name of the rules. Sliding rules may be followed by the
+    /// number of pieces and how they move, as in `sliding:3:anywhere`, and
+    /// vanishing rules by the number of marks kept, as in `vanishing:4`, and
+    /// multiplayer rules by the number of players, as in `multiplayer:4`.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let unknown =
+            || io::Error::new(io::ErrorKind::InvalidInput, format!("unknown rules: {}", s));
+        let fields: Vec<&str> = s.split(':').collect();
+        match fields[..] {
+            ["classic"] => Ok(Self::Classic),
+            ["misere"] => Ok(Self::Misere),
+            ["wild"] => Ok(Self::Wild),
+            ["order-and-chaos"] => Ok(Self::OrderAndChaos),
+            ["sliding", ref options @ ..] if options.len() <= 2 => {
+                let pieces = match options.first() {
+                    Some(pieces) => pieces.parse().map_err(|_| unknown())?,
+                    None => DEFAULT_PIECES,
+                };
+                let movement = match options.get(1) {
+                    None | Some(&"adjacent") => Movement::Adjacent,
+                    Some(&"anywhere") => Movement::Anywhere,
+                    Some(_) => return Err(unknown()),
+                };
+                if pieces == 0 {
+                    return Err(unknown());
+                }
+                Ok(Self::Sliding { pieces, movement })
+            }
+            ["vanishing"] => Ok(Self::Vanishing {
+                marks: DEFAULT_MARKS,
+            }),
+            ["vanishing", marks] => match marks.parse() {
+                Ok(0) | Err(_) => Err(unknown()),
+                Ok(marks) => Ok(Self::Vanishing { marks }),
+            },
+            ["multiplayer"] => Ok(Self::Multiplayer {
+                players: DEFAULT_PLAYERS,
+            }),
+            ["multiplayer", players] => match players.parse() {
+                Ok(players) if players > 2 && usize::from(players) <= Player::ALL.len() => {
+                    Ok(Self::Multiplayer { players })
+                }
+                _ => Err(unknown()),
+            },
+            _ => Err(unknown()),
+        }
+    }
+}
+
+/// What choosing a position on the board comes to for the player to move;
+/// see `pick_move`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pick {
+    /// The move to play.
+    Move(Move),
+    /// The player's piece at the position is picked up, to be moved to the
+    /// next position chosen.
+    PickUp(Coordinates),
+    /// The piece picked up is put back where it was.
+    PutBack(Coordinates),
+}
+
+/// Picks the move the player makes by choosing the position, placing the
+/// given symbol, much as a person would on the board. Once the player gets
+/// to choose which of their pieces to move, as under sliding rules, the
+/// first choice picks up a piece, given as `selected` after, and the second
+/// puts it down; choosing the piece picked up again puts it back.
+pub fn pick_move(
+    board: &Board,
+    rules: &dyn Rules,
+    player: Player,
+    symbol: Player,
+    position: Coordinates,
+    selected: Option<Coordinates>,
+) -> io::Result<Pick> {
+    let legal_moves = rules.legal_moves(board, player);
+    let mut pieces = legal_moves.iter().filter_map(|next| next.from);
+    let picks_up_pieces = match pieces.next() {
+        Some(first) => pieces.any(|from| from != first),
+        None => false,
+    };
+    let next = if !picks_up_pieces {
+        // Under gravity the mark drops to the bottom of the column.
+        let target = if board.gravity {
+            board
+                .drop_position(&position)
+                .ok_or_else(|| io::Error::other("the column is full"))?
+        } else {
+            position
+        };
+        // Placing a mark may also take the oldest one off the board.
+        legal_moves
+            .into_iter()
+            .find(|next| next.position == target && next.symbol == symbol)
+    } else {
+        match selected {
+            None => {
+                if !legal_moves.iter().any(|next| next.from == Some(position)) {
+                    return Err(io::Error::other("there is no piece to move there"));
+                }
+                return Ok(Pick::PickUp(position));
+            }
+            Some(from) if from == position => return Ok(Pick::PutBack(from)),
+            Some(from) => legal_moves
+                .into_iter()
+                .find(|next| next.position == position && next.from == Some(from)),
+        }
+    };
+    next.map(Pick::Move)
+        .ok_or_else(|| io::Error::other("that move is not allowed"))
+}
+
+/// Every way to place one of the given symbols on a position the board lets
+/// the next mark go on.
+fn placements(board: &Board, symbols: &[Player]) -> Vec<Move> {
+    board
+        .legal_moves()
+        .into_iter()
+        .flat_map(|position| {
+            symbols
+                .iter()
+                .map(move |symbol| Move::place(position, *symbol))
+        })
+        .collect()
+}
+
+/// How much better the player stands than their opponent on the lines which
+/// only one of them has marked.
+fn line_balance(board: &Board, player: Player) -> i32 {
+    let mut score = 0;
+    for line in board.lines() {
+        let mut own = 0;
+        let mut other = 0;
+        for position in &line {
+            match board.get(position) {
+                Some(marker) if *marker == player => own += 1,
+                Some(_) => other += 1,
+                None => {}
+            }
+        }
+        if own > 0 && other > 0 {
+            continue;
+        }
+        score += own * own - other * other;
+    }
+    score
+}
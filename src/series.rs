@@ -0,0 +1,251 @@
+use super::game::{print_message, Seat};
+use super::Outcome;
+
+use crossterm::event::{read, Event, KeyCode};
+use crossterm::{cursor, execute};
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+
+/// A best-of-N match between the participants seated for the first game.
+/// Unless told otherwise, the seats move round after every game so that each
+/// participant gets to move first in turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub best_of: u16,
+    /// Who each participant is, in the order they move in the first game.
+    pub seats: Vec<Seat>,
+    /// Whether the participants take turns moving first. Games opened under
+    /// a swap rule keep their seats and take turns opening instead.
+    pub rotate_seats: bool,
+    /// The winning participant of every finished game, or `None` for a draw.
+    pub results: Vec<Option<usize>>,
+}
+
+/// What to do once a game is over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NextGame {
+    /// Play the next game of the series, or a new series once it is over.
+    Rematch,
+    /// Pick new settings and start a new series with them.
+    NewSettings,
+    /// Show the player's statistics.
+    Stats,
+    Quit,
+}
+
+impl Series {
+    pub fn new(best_of: u16, seats: Vec<Seat>) -> Self {
+        Self {
+            best_of,
+            seats,
+            rotate_seats: true,
+            results: Vec::new(),
+        }
+    }
+
+    /// The seats of the next game, one for each player in the order of
+    /// `Player::ALL`.
+    pub fn seats_for_next_game(&self) -> Vec<Seat> {
+        let n = self.seats.len();
+        (0..n)
+            .map(|player| self.seats[self.participant(player, self.results.len())])
+            .collect()
+    }
+
+    /// The participant who plays the player with the given index in the
+    /// given game, counting from zero.
+    fn participant(&self, player: usize, game: usize) -> usize {
+        if self.rotate_seats {
+            (player + game) % self.seats.len()
+        } else {
+            player
+        }
+    }
+
+    /// Counts the outcome of the game just played.
+    pub fn record(&mut self, outcome: Outcome) {
+        let game = self.results.len();
+        let winner = match outcome {
+            Outcome::Win(player) => Some(self.participant(player.index(), game)),
+            Outcome::Draw => None,
+        };
+        self.results.push(winner);
+    }
+
+    pub fn wins(&self, participant: usize) -> usize {
+        self.results
+            .iter()
+            .filter(|winner| **winner == Some(participant))
+            .count()
+    }
+
+    pub fn losses(&self, participant: usize) -> usize {
+        self.results
+            .iter()
+            .filter(|winner| matches!(winner, Some(other) if *other != participant))
+            .count()
+    }
+
+    pub fn draws(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|winner| winner.is_none())
+            .count()
+    }
+
+    /// Whether every game has been played or a participant has won more
+    /// than half of them, so that nobody can catch up.
+    pub fn is_over(&self) -> bool {
+        self.results.len() >= usize::from(self.best_of) || self.winner().is_some()
+    }
+
+    /// The participant who has won more than half of the games.
+    pub fn winner(&self) -> Option<usize> {
+        (0..self.seats.len())
+            .find(|participant| 2 * self.wins(*participant) > usize::from(self.best_of))
+    }
+
+    /// How the participant is called: "You" for the only person at the
+    /// keyboard and "AI" for a single AI, and by their seat otherwise.
+    pub fn name(&self, participant: usize) -> String {
+        let seat = self.seats[participant];
+        let count = self.seats.iter().filter(|other| **other == seat).count();
+        match seat {
+            Seat::Human if count == 1 => "You".to_string(),
+            Seat::Ai if count == 1 => "AI".to_string(),
+            Seat::Human => format!("Human {}", participant + 1),
+            Seat::Ai => format!("AI {}", participant + 1),
+        }
+    }
+
+    /// A single line with the wins, losses and draws of every participant,
+    /// such as `Best of 3, game 2: You W1 L0 D0  AI W0 L1 D0`.
+    pub fn scoreboard(&self) -> String {
+        let game = (self.results.len() + 1).min(usize::from(self.best_of));
+        let scores: Vec<String> = (0..self.seats.len())
+            .map(|participant| {
+                format!(
+                    "{} W{} L{} D{}",
+                    self.name(participant),
+                    self.wins(participant),
+                    self.losses(participant),
+                    self.draws()
+                )
+            })
+            .collect();
+        format!(
+            "Best of {}, game {}: {}",
+            self.best_of,
+            game,
+            scores.join("  ")
+        )
+    }
+
+    /// How the series went, with a line for every participant.
+    pub fn summary(&self) -> String {
+        let games = self.results.len();
+        let result = match self.winner() {
+            Some(winner) if self.name(winner) == "You" => "you won".to_string(),
+            Some(winner) => format!("{} won", self.name(winner)),
+            None if self.is_over() => "drawn".to_string(),
+            None => "unfinished".to_string(),
+        };
+        let mut summary = format!(
+            "Best of {} after {} game{}: {}\n",
+            self.best_of,
+            games,
+            if games == 1 { "" } else { "s" },
+            result
+        );
+        for participant in 0..self.seats.len() {
+            summary += &format!(
+                "  {}: {} won, {} lost, {} drawn\n",
+                self.name(participant),
+                self.wins(participant),
+                self.losses(participant),
+                self.draws()
+            );
+        }
+        summary
+    }
+}
+
+/// Shows what can be done once a game is over on the given screen row and
+/// waits for a choice. Esc quits.
+pub fn ask_next_game(row: u16, series_over: bool) -> crossterm::Result<NextGame> {
+    let rematch = if series_over { "rematch" } else { "next game" };
+    print_message(
+        row,
+        &format!("r: {}  n: new settings  s: stats  Esc: quit", rematch),
+    )?;
+    loop {
+        if let Event::Key(k) = read()? {
+            match k.code {
+                KeyCode::Char('r') => return Ok(NextGame::Rematch),
+                KeyCode::Char('n') => return Ok(NextGame::NewSettings),
+                KeyCode::Char('s') => return Ok(NextGame::Stats),
+                KeyCode::Esc => return Ok(NextGame::Quit),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Prints lines of text one below the other, starting on the given screen
+/// row.
+pub fn print_lines(row: u16, lines: &[String]) -> crossterm::Result<()> {
+    for (i, line) in lines.iter().enumerate() {
+        print_message(row + i as u16, line)?;
+    }
+    Ok(())
+}
+
+/// Asks for a line of text on the given screen row, such as new settings.
+/// Returns `None` if Esc is pressed instead.
+pub fn read_line(row: u16, prompt: &str) -> crossterm::Result<Option<String>> {
+    read_line_completing(row, prompt, &str::to_string)
+}
+
+/// Like `read_line`, but Tab replaces the line typed so far with what
+/// `complete` makes of it.
+pub fn read_line_completing(
+    row: u16,
+    prompt: &str,
+    complete: &dyn Fn(&str) -> String,
+) -> crossterm::Result<Option<String>> {
+    let mut line = String::new();
+    loop {
+        print_message(row, &format!("{}{}", prompt, line))?;
+        execute!(
+            stdout(),
+            cursor::MoveTo((prompt.len() + line.len()) as u16, row)
+        )?;
+        if let Event::Key(k) = read()? {
+            match k.code {
+                KeyCode::Enter => return Ok(Some(line)),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                KeyCode::Tab => line = complete(&line),
+                KeyCode::Char(c) => line.push(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The file the given game of a session is saved to: the one given by
+/// `--save` for the first game, and then the same name numbered, as in
+/// `game-2.txt`.
+pub fn game_path(path: &Path, game: usize) -> PathBuf {
+    if game == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, game, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, game),
+    };
+    path.with_file_name(name)
+}
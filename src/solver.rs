@@ -0,0 +1,153 @@
+use super::board::Board;
+use super::{Coordinates, Outcome, Player};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Number of distinct terminal positions, up to symmetry, for each way a game
+/// can end.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TerminalPositions {
+    pub cross_wins: usize,
+    pub zero_wins: usize,
+    pub draws: usize,
+}
+
+/// The result of exhaustively solving an m,n,k-game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub width: u16,
+    pub height: u16,
+    pub win_length: u16,
+    /// The outcome of the game when both players play perfectly.
+    pub value: Outcome,
+    /// Number of distinct positions, up to symmetry, reachable from the empty
+    /// board, including the empty board itself.
+    pub positions: usize,
+    pub terminal_positions: TerminalPositions,
+    /// Every first move which achieves the game-theoretic value.
+    pub best_moves: Vec<Coordinates>,
+}
+
+/// Exhaustively solves the m,n,k-game with the given dimensions, where
+/// `Player::Cross` moves first.
+///
+/// Every position is visited once up to symmetry; the table of solved
+/// positions is keyed on the board's canonical hash and stores only a single
+/// byte per position, which keeps 4x4 boards well within laptop memory.
+pub fn solve(width: u16, height: u16, win_length: u16) -> Solution {
+    let mut solver = Solver::default();
+    let mut board = Board::new(width, height, win_length);
+    let value = solver.value(&mut board, Player::Cross, None);
+    let best_moves = board
+        .empty_positions()
+        .into_iter()
+        .filter(|position| {
+            board.mark(*position, Player::Cross).unwrap();
+            let child = solver.value(&mut board, Player::Zero, Some(*position));
+            board.unmark(position);
+            child == value
+        })
+        .collect();
+    Solution {
+        width,
+        height,
+        win_length,
+        value: to_outcome(value),
+        positions: solver.values.len(),
+        terminal_positions: solver.terminal_positions,
+        best_moves,
+    }
+}
+
+#[derive(Default)]
+struct Solver {
+    /// Solved positions by canonical hash. Values are +1 if `Player::Cross`
+    /// wins, -1 if `Player::Zero` wins and 0 for a draw.
+    values: HashMap<u64, i8>,
+    terminal_positions: TerminalPositions,
+}
+
+impl Solver {
+    fn value(&mut self, board: &mut Board, to_move: Player, last_move: Option<Coordinates>) -> i8 {
+        let key = board.canonical_hash();
+        if let Some(value) = self.values.get(&key) {
+            return *value;
+        }
+        let last_player = to_move.opponent();
+        let value =
+            if last_move.is_some_and(|position| board.completes_line(&position, &last_player)) {
+                match last_player {
+                    Player::Cross => self.terminal_positions.cross_wins += 1,
+                    Player::Zero => self.terminal_positions.zero_wins += 1,
+                    _ => unreachable!("only crosses and zeros play solved games"),
+                }
+                score(last_player)
+            } else if board.is_full() {
+                self.terminal_positions.draws += 1;
+                0
+            } else {
+                // Every reply is explored, even after finding a winning one, so
+                // that the position counts cover the whole game tree.
+                let mut best = -score(to_move);
+                for position in board.empty_positions() {
+                    board.mark(position, to_move).unwrap();
+                    let child = self.value(board, to_move.opponent(), Some(position));
+                    board.unmark(&position);
+                    if child * score(to_move) > best * score(to_move) {
+                        best = child;
+                    }
+                }
+                best
+            };
+        self.values.insert(key, value);
+        value
+    }
+}
+
+fn score(player: Player) -> i8 {
+    match player {
+        Player::Cross => 1,
+        Player::Zero => -1,
+        _ => unreachable!("only crosses and zeros play solved games"),
+    }
+}
+
+fn to_outcome(value: i8) -> Outcome {
+    match value {
+        1 => Outcome::Win(Player::Cross),
+        -1 => Outcome::Win(Player::Zero),
+        _ => Outcome::Draw,
+    }
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Board: {}x{}, {} in a row",
+            self.width, self.height, self.win_length
+        )?;
+        let value = match self.value {
+            Outcome::Win(player) => format!("{} wins", player.to_char()),
+            Outcome::Draw => "draw".to_string(),
+        };
+        writeln!(f, "Value with perfect play: {}", value)?;
+        writeln!(
+            f,
+            "Reachable positions (up to symmetry): {}",
+            self.positions
+        )?;
+        let terminal = &self.terminal_positions;
+        writeln!(
+            f,
+            "Terminal positions (up to symmetry): {} ({} X wins, {} 0 wins, {} draws)",
+            terminal.cross_wins + terminal.zero_wins + terminal.draws,
+            terminal.cross_wins,
+            terminal.zero_wins,
+            terminal.draws
+        )?;
+        let best_moves: Vec<String> = self.best_moves.iter().map(Coordinates::to_string).collect();
+        write!(f, "Optimal first moves: {}", best_moves.join(" "))
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Name of the profile games are counted for unless another one is picked.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// How a game ended for the player whose statistics are kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// A finished game as counted in a player's statistics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayedGame {
+    /// Name of the AI played against, as picked on the command line.
+    pub opponent: String,
+    /// The board played on, written as `WxHxK`, with the number of layers
+    /// after the height on boards of several layers, as in `4x4x4x4`.
+    pub board: String,
+    pub result: GameResult,
+    /// Number of moves played in the game.
+    pub moves: usize,
+}
+
+/// Every game a player has finished, oldest first.
+///
+/// Statistics are stored as plain text with a line for every game, giving
+/// the opponent, the board, the result and the number of moves:
+///
+/// ```text
+/// game minimax 3x3x3 draw 9
+/// game mcts 6x6x4 win 17
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub games: Vec<PlayedGame>,
+}
+
+/// Wins, losses and draws.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tally {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl Tally {
+    fn count(&mut self, result: GameResult) {
+        match result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Loss => self.losses += 1,
+            GameResult::Draw => self.draws += 1,
+        }
+    }
+}
+
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} won, {} lost, {} drawn",
+            self.wins, self.losses, self.draws
+        )
+    }
+}
+
+impl Stats {
+    /// Reads the statistics from the given file; a missing file holds none.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Adds a game to the statistics stored in the given file, creating the
+    /// file and its directory if need be.
+    pub fn append(path: &Path, game: &PlayedGame) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", game)
+    }
+
+    /// Wins, losses and draws over every game.
+    pub fn total(&self) -> Tally {
+        let mut tally = Tally::default();
+        for game in &self.games {
+            tally.count(game.result);
+        }
+        tally
+    }
+
+    /// Wins, losses and draws against each AI.
+    pub fn by_opponent(&self) -> BTreeMap<&str, Tally> {
+        let mut tallies = BTreeMap::new();
+        for game in &self.games {
+            tallies
+                .entry(game.opponent.as_str())
+                .or_insert_with(Tally::default)
+                .count(game.result);
+        }
+        tallies
+    }
+
+    /// Number of games played on each board.
+    pub fn by_board(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for game in &self.games {
+            *counts.entry(game.board.as_str()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Average number of moves in a game, if any were played.
+    pub fn average_length(&self) -> Option<f64> {
+        if self.games.is_empty() {
+            return None;
+        }
+        let moves: usize = self.games.iter().map(|game| game.moves).sum();
+        Some(moves as f64 / self.games.len() as f64)
+    }
+
+    /// The result of the latest games and how many of them in a row ended
+    /// that way.
+    pub fn current_streak(&self) -> Option<(GameResult, usize)> {
+        let last = self.games.last()?.result;
+        let length = self
+            .games
+            .iter()
+            .rev()
+            .take_while(|game| game.result == last)
+            .count();
+        Some((last, length))
+    }
+
+    /// The most games won in a row.
+    pub fn longest_winning_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        for game in &self.games {
+            if game.result == GameResult::Win {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        longest
+    }
+
+    /// The statistics as lines of text for the given profile, as shown by the
+    /// `stats` command and on screen after a game.
+    pub fn report(&self, profile: &str) -> Vec<String> {
+        let games = self.games.len();
+        let mut lines = vec![format!(
+            "Statistics of {}: {} game{} played",
+            profile,
+            games,
+            if games == 1 { "" } else { "s" }
+        )];
+        if self.games.is_empty() {
+            return lines;
+        }
+        lines.push(format!("  Overall: {}", self.total()));
+        for (opponent, tally) in self.by_opponent() {
+            lines.push(format!("  Against {}: {}", opponent, tally));
+        }
+        let boards: Vec<String> = self
+            .by_board()
+            .iter()
+            .map(|(board, games)| format!("{} ({})", board, games))
+            .collect();
+        lines.push(format!("  Boards: {}", boards.join(", ")));
+        if let Some(average) = self.average_length() {
+            lines.push(format!("  Average game: {:.1} moves", average));
+        }
+        if let Some((result, length)) = self.current_streak() {
+            let result = match result {
+                GameResult::Win => "won",
+                GameResult::Loss => "lost",
+                GameResult::Draw => "drawn",
+            };
+            lines.push(format!(
+                "  Current streak: {} {} in a row, longest winning streak: {}",
+                length,
+                result,
+                self.longest_winning_streak()
+            ));
+        }
+        lines
+    }
+}
+
+/// The directory statistics are kept in: `tictactoe` under
+/// `$XDG_DATA_HOME`, or under `~/.local/share` if that is not set.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join("tictactoe"))
+}
+
+/// The file the statistics of the given profile are kept in, if there is a
+/// data directory.
+pub fn profile_path(profile: &str) -> Option<PathBuf> {
+    Some(data_dir()?.join(format!("{}.stats", profile)))
+}
+
+/// Checks that the profile name is fit to name a file: letters, digits, `-`
+/// and `_` only.
+pub fn check_profile(profile: &str) -> io::Result<()> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid profile name: {}", profile),
+        ))
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Win => "win",
+            Self::Loss => "loss",
+            Self::Draw => "draw",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "win" => Ok(Self::Win),
+            "loss" => Ok(Self::Loss),
+            "draw" => Ok(Self::Draw),
+            _ => Err(invalid_stats(s)),
+        }
+    }
+}
+
+impl fmt::Display for PlayedGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "game {} {} {} {}",
+            self.opponent, self.board, self.result, self.moves
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for game in &self.games {
+            writeln!(f, "{}", game)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Stats {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        let mut games = Vec::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["game", opponent, board, result, moves] => games.push(PlayedGame {
+                    opponent: opponent.to_string(),
+                    board: board.to_string(),
+                    result: result.parse().map_err(|_| invalid_stats(line))?,
+                    moves: moves.parse().map_err(|_| invalid_stats(line))?,
+                }),
+                _ => return Err(invalid_stats(line)),
+            }
+        }
+        Ok(Self { games })
+    }
+}
+
+fn invalid_stats(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid statistics: {}", line),
+    )
+}
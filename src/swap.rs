@@ -0,0 +1,51 @@
+use super::Player;
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// A swap rule to take away the first player's advantage: one player opens
+/// the game by placing a few stones, after which the other player chooses
+/// which side to play.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Swap {
+    /// The pie rule: the opener places a single stone of their own.
+    Pie,
+    /// As in Swap2 for Gomoku, the opener places three stones: two of their
+    /// own and one of their opponent's in between.
+    Swap2,
+}
+
+impl Swap {
+    /// The symbols of the stones the opener places, in order.
+    pub fn opening(&self, opener: Player) -> Vec<Player> {
+        match self {
+            Self::Pie => vec![opener],
+            Self::Swap2 => vec![opener, opener.opponent(), opener],
+        }
+    }
+}
+
+impl fmt::Display for Swap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Pie => write!(f, "pie"),
+            Self::Swap2 => write!(f, "swap2"),
+        }
+    }
+}
+
+impl FromStr for Swap {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<Self> {
+        match s {
+            "pie" => Ok(Self::Pie),
+            "swap2" => Ok(Self::Swap2),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown swap rule: {}", s),
+            )),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::ai::{AI, DEFAULT_THINK_TIME};
+use super::board::Board;
+use super::game::{print_message, read_input_event};
+use super::grid::Grid;
+use super::mcts::Playable;
+use super::rules::{Rules, Variant};
+use super::{Coordinates, InputEvent, Outcome, Player, Side};
+
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::io;
+use std::io::stdout;
+use std::time::{Duration, Instant};
+
+/// Number of positions along each side of the whole board.
+pub const SIDE: i16 = 9;
+
+/// Ultimate tic-tac-toe: nine classic 3x3 boards laid out in a 3x3 square.
+/// Winning a small board claims its square on the big board, and the position
+/// of every move within its small board decides which small board the opponent
+/// has to play on next.
+///
+/// Positions are given in coordinates of the whole 9x9 board.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UltimateBoard {
+    boards: Vec<Board>,
+    /// How each small board ended, once it has.
+    pub results: [Option<Outcome>; 9],
+    /// The big board, with a mark for every small board that has been won.
+    pub big_board: Board,
+    /// The small board the player to move has to play on, or `None` if they
+    /// may play on any small board which is still open.
+    pub active: Option<usize>,
+    pub to_move: Player,
+    outcome: Option<Outcome>,
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        Self {
+            boards: vec![Board::from(Side(3)); 9],
+            results: [None; 9],
+            big_board: Board::from(Side(3)),
+            active: None,
+            to_move: Player::Cross,
+            outcome: None,
+        }
+    }
+}
+
+impl UltimateBoard {
+    /// Index of the small board a position lies on, counting in rows from the
+    /// top-left board.
+    pub fn board_index(position: &Coordinates) -> usize {
+        (position.y / 3 * 3 + position.x / 3) as usize
+    }
+
+    /// The position within its small board.
+    pub fn local_position(position: &Coordinates) -> Coordinates {
+        Coordinates {
+            x: position.x % 3,
+            y: position.y % 3,
+            z: 0,
+        }
+    }
+
+    pub fn board(&self, index: usize) -> &Board {
+        &self.boards[index]
+    }
+
+    pub fn get(&self, position: &Coordinates) -> Option<&Player> {
+        self.boards[Self::board_index(position)].get(&Self::local_position(position))
+    }
+
+    pub fn is_open(&self, index: usize) -> bool {
+        self.results[index].is_none()
+    }
+
+    /// Whether the small board is one the player to move may play on.
+    pub fn is_playable(&self, index: usize) -> bool {
+        self.outcome.is_none()
+            && self.is_open(index)
+            && self.active.is_none_or(|active| active == index)
+    }
+
+    pub fn is_legal(&self, position: &Coordinates) -> bool {
+        (0..SIDE).contains(&position.x)
+            && (0..SIDE).contains(&position.y)
+            && self.is_playable(Self::board_index(position))
+            && self.get(position).is_none()
+    }
+
+    /// Places the mark of the player to move and passes the turn.
+    pub fn mark(&mut self, position: Coordinates) -> io::Result<()> {
+        if !self.is_legal(&position) {
+            return Err(io::Error::other("the position cannot be marked"));
+        }
+        let index = Self::board_index(&position);
+        let local = Self::local_position(&position);
+        let player = self.to_move;
+        self.boards[index].mark(local, player)?;
+        if let Some(result) = Variant::Classic.outcome(&self.boards[index], &local, player) {
+            self.results[index] = Some(result);
+            let big_position = Coordinates {
+                x: index as i16 % 3,
+                y: index as i16 / 3,
+                z: 0,
+            };
+            if result == Outcome::Win(player) {
+                self.big_board.mark(big_position, player)?;
+                if self.big_board.completes_line(&big_position, &player) {
+                    self.outcome = Some(Outcome::Win(player));
+                }
+            }
+            if self.outcome.is_none() && self.results.iter().all(Option::is_some) {
+                self.outcome = Some(Outcome::Draw);
+            }
+        }
+        let next = (local.y * 3 + local.x) as usize;
+        self.active = if self.is_open(next) { Some(next) } else { None };
+        self.to_move = player.opponent();
+        Ok(())
+    }
+}
+
+impl Playable for UltimateBoard {
+    type Move = Coordinates;
+
+    fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    fn legal_moves(&self) -> Vec<Coordinates> {
+        let mut moves = Vec::new();
+        for index in (0..9).filter(|index| self.is_playable(*index)) {
+            let origin = Coordinates {
+                x: index as i16 % 3 * 3,
+                y: index as i16 / 3 * 3,
+                z: 0,
+            };
+            for local in self.boards[index].empty_positions() {
+                moves.push(origin + local);
+            }
+        }
+        moves
+    }
+
+    fn play(&mut self, position: Coordinates) {
+        self.mark(position).expect("only legal moves are played");
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+}
+
+/// Draws the nine small boards with a gap between them, highlighting the
+/// boards the player to move may play on and greying out finished ones.
+#[derive(Debug, Default, PartialEq)]
+pub struct UltimateGrid;
+
+impl UltimateGrid {
+    /// Width of a small board on the screen, not counting the gap after it.
+    const BOARD_WIDTH: i16 = 11;
+    const GAP: i16 = 2;
+
+    /// Screen coordinates of a position on the whole board.
+    pub fn grid_coords_to_screen_coords(position: &Coordinates) -> Coordinates {
+        Coordinates {
+            x: position.x / 3 * (Self::BOARD_WIDTH + Self::GAP) + position.x % 3 * 4 + 1,
+            y: position.y / 3 * 4 + position.y % 3,
+            z: 0,
+        }
+    }
+
+    /// Screen row below the whole board, used for messages.
+    pub fn message_row() -> u16 {
+        (SIDE / 3 * 4) as u16
+    }
+
+    pub fn draw(&self, board: &UltimateBoard) -> crossterm::Result<()> {
+        for index in 0..9 {
+            let background = if !board.is_open(index) {
+                Color::DarkGrey
+            } else if board.is_playable(index) {
+                Color::Yellow
+            } else {
+                Color::White
+            };
+            for y in 0..3 {
+                let row_start = Self::grid_coords_to_screen_coords(&Coordinates {
+                    x: index as i16 % 3 * 3,
+                    y: index as i16 / 3 * 3 + y,
+                    z: 0,
+                });
+                execute!(
+                    stdout(),
+                    cursor::MoveTo(row_start.x as u16 - 1, row_start.y as u16),
+                    SetBackgroundColor(background)
+                )?;
+                for x in 0..3 {
+                    let marker = board.boards[index]
+                        .get(&Coordinates { x, y, z: 0 })
+                        .map_or(' ', Player::to_char);
+                    execute!(
+                        stdout(),
+                        Print(" "),
+                        SetForegroundColor(Color::Red),
+                        Print(marker),
+                        SetForegroundColor(Color::Black),
+                        Print(if x < 2 { " |" } else { " " })
+                    )?;
+                }
+                execute!(stdout(), ResetColor)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A game of ultimate tic-tac-toe between the player, who plays crosses and
+/// moves first, and the AI.
+#[derive(Debug, PartialEq)]
+pub struct UltimateTicTacToe {
+    pub cursor: Coordinates,
+    pub grid: UltimateGrid,
+    pub ai_algo: AI,
+    pub board: UltimateBoard,
+    /// The most time the AI may think about a single move.
+    pub ai_time: Duration,
+    rng: StdRng,
+}
+
+impl UltimateTicTacToe {
+    pub fn from_seed(ai_algo: AI, seed: u64) -> Self {
+        Self {
+            cursor: Coordinates { x: 4, y: 4, z: 0 },
+            grid: UltimateGrid,
+            ai_algo,
+            board: UltimateBoard::default(),
+            ai_time: DEFAULT_THINK_TIME,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The game loop reads player input and performs actions based on this input.
+    pub fn game_loop(&mut self) -> crossterm::Result<()> {
+        Grid::cleanup()?;
+        self.draw()?;
+        loop {
+            match read_input_event(None)? {
+                InputEvent::Direction(direction) => {
+                    let mut position = self.cursor + direction.get_relative_coords();
+                    position.x = position.x.clamp(0, SIDE - 1);
+                    position.y = position.y.clamp(0, SIDE - 1);
+                    // The board has no layers to move between.
+                    position.z = 0;
+                    self.set_cursor(position)?;
+                }
+                InputEvent::Quit => break,
+                InputEvent::Tick | InputEvent::ToggleSymbol | InputEvent::Prompt => {}
+                InputEvent::Mark => {
+                    // Let's ignore moves outside the boards the player may play on.
+                    if self.board.mark(self.cursor).is_err() {
+                        continue;
+                    }
+                    self.draw()?;
+                    if let Some(outcome) = self.board.outcome() {
+                        self.announce(outcome)?;
+                        break;
+                    }
+                    let deadline = Instant::now() + self.ai_time;
+                    let ai_cursor =
+                        self.ai_algo
+                            .get_ultimate_marker(&self.board, &mut self.rng, deadline);
+                    self.board.mark(ai_cursor)?;
+                    self.draw()?;
+                    if let Some(outcome) = self.board.outcome() {
+                        self.announce(outcome)?;
+                        break;
+                    }
+                    // Bring the cursor over to the board the player has to play on.
+                    if let Some(active) = self.board.active {
+                        if UltimateBoard::board_index(&self.cursor) != active {
+                            self.set_cursor(Coordinates {
+                                x: active as i16 % 3 * 3 + 1,
+                                y: active as i16 / 3 * 3 + 1,
+                                z: 0,
+                            })?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&self) -> crossterm::Result<()> {
+        self.grid.draw(&self.board)?;
+        Self::move_cursor_to_grid(&self.cursor)
+    }
+
+    fn set_cursor(&mut self, position: Coordinates) -> crossterm::Result<()> {
+        self.cursor = position;
+        Self::move_cursor_to_grid(&position)
+    }
+
+    fn move_cursor_to_grid(position: &Coordinates) -> crossterm::Result<()> {
+        let screen_coords = UltimateGrid::grid_coords_to_screen_coords(position);
+        execute!(
+            stdout(),
+            cursor::MoveTo(screen_coords.x as u16, screen_coords.y as u16)
+        )?;
+        Ok(())
+    }
+
+    fn announce(&self, outcome: Outcome) -> crossterm::Result<()> {
+        let msg = match outcome {
+            Outcome::Win(Player::Cross) => "You've won the game!",
+            Outcome::Win(_) => "AI won the game!",
+            Outcome::Draw => "The game was a draw!",
+        };
+        print_message(UltimateGrid::message_row(), msg)
+    }
+}
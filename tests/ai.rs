@@ -1,19 +1,326 @@
-use std::collections::HashMap;
-use tictactoe::{Coordinates, Player, Side, AI};
-
-#[test]
-fn get_marker() {
-    let side = Side(3);
-    let marked_positions: HashMap<Coordinates, Player> = [
-        (Coordinates { x: 1, y: 0 }, Player::Cross),
-        (Coordinates { x: 2, y: 1 }, Player::Zero),
-        (Coordinates { x: 1, y: 2 }, Player::Cross),
-    ]
-    .iter()
-    .cloned()
-    .collect();
-    let marker = AI::Random.get_marker(&marked_positions, &side);
-    let positions: Vec<Coordinates> = marked_positions.keys().cloned().collect();
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+use tictactoe::{Board, Coordinates, Move, Player, Side, Variant, AI};
+
+#[test]
+fn get_marker() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 2, z: 0 }, Player::Cross)?;
+    let marker = AI::Random.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Zero,
+        &mut rand::thread_rng(),
+    );
+    let positions: Vec<Coordinates> = board.marked_positions().keys().cloned().collect();
     let is_new_move = !positions.contains(&marker);
-    assert!(is_new_move)
+    assert!(is_new_move);
+    Ok(())
+}
+
+#[test]
+fn seeded_get_marker_is_reproducible() -> std::io::Result<()> {
+    let mut board = Board::from(Side(4));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    let mut first_rng = StdRng::seed_from_u64(7);
+    let mut second_rng = StdRng::seed_from_u64(7);
+    for _ in 0..10 {
+        assert_eq!(
+            AI::Random.get_marker(&board, &Variant::Classic, Player::Zero, &mut first_rng),
+            AI::Random.get_marker(&board, &Variant::Classic, Player::Zero, &mut second_rng)
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn minimax_takes_the_win() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 0, y: 2, z: 0 }, Player::Cross)?;
+    let marker = AI::MiniMax.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(marker, Coordinates { x: 2, y: 2, z: 0 });
+    Ok(())
+}
+
+#[test]
+fn minimax_takes_the_win_through_layers() -> std::io::Result<()> {
+    let mut board = Board::layered(4, 4, 4, 4);
+    for z in 0..3 {
+        board.mark(Coordinates { x: 1, y: 2, z }, Player::Zero)?;
+    }
+    for x in 0..3 {
+        board.mark(Coordinates { x, y: 0, z: 0 }, Player::Cross)?;
+    }
+    let marker = AI::MiniMax.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(marker, Coordinates { x: 1, y: 2, z: 3 });
+    Ok(())
+}
+
+#[test]
+fn minimax_plays_connect_four() -> std::io::Result<()> {
+    let mut board = Board::new(7, 6, 4);
+    board.gravity = true;
+    for x in 1..4 {
+        board.mark(Coordinates { x, y: 5, z: 0 }, Player::Cross)?;
+        board.mark(Coordinates { x, y: 4, z: 0 }, Player::Zero)?;
+    }
+    let mut rng = StdRng::seed_from_u64(0);
+    let marker = AI::MiniMax.get_marker(&board, &Variant::Classic, Player::Cross, &mut rng);
+    assert!(
+        marker == Coordinates { x: 0, y: 5, z: 0 } || marker == Coordinates { x: 4, y: 5, z: 0 }
+    );
+    for _ in 0..20 {
+        let marker = AI::Random.get_marker(&board, &Variant::Classic, Player::Cross, &mut rng);
+        assert!(board.legal_moves().contains(&marker));
+    }
+    Ok(())
+}
+
+#[test]
+fn minimax_wins_on_broken_diagonal() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.torus = true;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 2, z: 0 }, Player::Zero)?;
+    let marker = AI::MiniMax.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Cross,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(marker, Coordinates { x: 0, y: 2, z: 0 });
+    Ok(())
+}
+
+#[test]
+fn minimax_completes_either_symbol_in_wild() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Zero)?;
+    let next = AI::MiniMax.get_move_within(
+        &board,
+        &Variant::Wild,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+        Instant::now() + Duration::from_secs(1),
+    );
+    assert_eq!(
+        next,
+        Move::place(Coordinates { x: 2, y: 2, z: 0 }, Player::Cross)
+    );
+    Ok(())
+}
+
+#[test]
+fn minimax_slides_into_a_line() -> std::io::Result<()> {
+    let rules: Variant = "sliding".parse()?;
+    let mut board = Board::from(Side(3));
+    for (x, y) in [(0, 0), (1, 0), (2, 1)].iter() {
+        board.mark(Coordinates { x: *x, y: *y, z: 0 }, Player::Cross)?;
+    }
+    for (x, y) in [(0, 1), (0, 2), (2, 2)].iter() {
+        board.mark(Coordinates { x: *x, y: *y, z: 0 }, Player::Zero)?;
+    }
+    let next = AI::MiniMax.get_move_within(
+        &board,
+        &rules,
+        Player::Cross,
+        &mut StdRng::seed_from_u64(0),
+        Instant::now() + Duration::from_secs(1),
+    );
+    assert_eq!(
+        next,
+        Move {
+            position: Coordinates { x: 2, y: 0, z: 0 },
+            symbol: Player::Cross,
+            from: Some(Coordinates { x: 2, y: 1, z: 0 }),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_plays_endless_games() {
+    // With two marks each nobody can ever get three in a row.
+    let board = Board::from(Side(3));
+    let marker = AI::MonteCarlo.get_marker_within(
+        &board,
+        &Variant::Vanishing { marks: 2 },
+        Player::Cross,
+        &mut StdRng::seed_from_u64(0),
+        Instant::now() + Duration::from_millis(200),
+    );
+    assert!(board.contains(&marker));
+}
+
+#[test]
+fn minimax_blocks_the_loss() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    let marker = AI::MiniMax.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(marker, Coordinates { x: 2, y: 0, z: 0 });
+    Ok(())
+}
+
+#[test]
+fn minimax_avoids_lines_in_misere() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 0, y: 1, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)?;
+    let marker = AI::MiniMax.get_marker(
+        &board,
+        &Variant::Misere,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_ne!(marker, Coordinates { x: 2, y: 0, z: 0 });
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_takes_the_win() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 0, y: 2, z: 0 }, Player::Cross)?;
+    let marker = AI::MonteCarlo.get_marker(
+        &board,
+        &Variant::Classic,
+        Player::Zero,
+        &mut StdRng::seed_from_u64(0),
+    );
+    assert_eq!(marker, Coordinates { x: 2, y: 2, z: 0 });
+    Ok(())
+}
+
+#[test]
+fn minimax_never_loses_to_random() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..5 {
+        let mut board = Board::from(Side(3));
+        let mut player = Player::Cross;
+        loop {
+            let ai = match player {
+                Player::Cross => AI::Random,
+                _ => AI::MiniMax,
+            };
+            let position = ai.get_marker(&board, &Variant::Classic, player, &mut rng);
+            board.mark(position, player).unwrap();
+            assert!(!board.has_line(&Player::Cross));
+            if board.has_line(&Player::Zero) || board.is_full() {
+                break;
+            }
+            player = player.opponent();
+        }
+    }
+}
+
+#[test]
+fn minimax_moves_when_out_of_time() {
+    let board = Board::new(7, 7, 4);
+    let deadline = Instant::now() - Duration::from_secs(1);
+    let marker = AI::MiniMax.get_marker_within(
+        &board,
+        &Variant::Classic,
+        Player::Cross,
+        &mut StdRng::seed_from_u64(0),
+        deadline,
+    );
+    assert!(board.contains(&marker));
+}
+
+#[test]
+fn minimax_respects_deadline() {
+    let board = Board::new(7, 7, 4);
+    let started = Instant::now();
+    AI::MiniMax.get_marker_within(
+        &board,
+        &Variant::Classic,
+        Player::Cross,
+        &mut StdRng::seed_from_u64(0),
+        started + Duration::from_millis(100),
+    );
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn from_str() {
+    assert_eq!("random".parse::<AI>().unwrap(), AI::Random);
+    assert_eq!("minimax".parse::<AI>().unwrap(), AI::MiniMax);
+    assert_eq!("mcts".parse::<AI>().unwrap(), AI::MonteCarlo);
+    assert!("deep-blue".parse::<AI>().is_err());
+}
+
+#[test]
+fn swaps_out_of_lost_positions() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)?;
+    let mut rng = StdRng::seed_from_u64(1);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    // The centre opening only leads to a draw, which is no reason to swap.
+    assert!(!AI::MiniMax.wants_swap(&board, &Variant::Classic, Player::Zero, &mut rng, deadline));
+    let mut board = Board::from(Side(3));
+    for (x, y) in [(0, 0), (2, 2), (2, 0)].iter() {
+        board.mark(Coordinates { x: *x, y: *y, z: 0 }, Player::Cross)?;
+    }
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 0, y: 2, z: 0 }, Player::Zero)?;
+    // Crosses threaten two lines at once, so zeros are lost.
+    assert!(AI::MiniMax.wants_swap(&board, &Variant::Classic, Player::Zero, &mut rng, deadline));
+    assert!(AI::MonteCarlo.wants_swap(&board, &Variant::Classic, Player::Zero, &mut rng, deadline));
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_plays_for_a_third_player() -> std::io::Result<()> {
+    let mut board = Board::new(4, 4, 3);
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Plus)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Plus)?;
+    board.mark(Coordinates { x: 0, y: 3, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 3, y: 3, z: 0 }, Player::Zero)?;
+    let rules = Variant::Multiplayer { players: 3 };
+    let mut rng = StdRng::seed_from_u64(5);
+    let next = AI::MonteCarlo.get_move_within(
+        &board,
+        &rules,
+        Player::Plus,
+        &mut rng,
+        Instant::now() + Duration::from_secs(1),
+    );
+    assert_eq!(
+        next,
+        Move::place(Coordinates { x: 2, y: 0, z: 0 }, Player::Plus)
+    );
+    Ok(())
 }
@@ -0,0 +1,172 @@
+// Each test file also builds on its own, so the helpers are loaded per file.
+#[allow(clippy::duplicate_mod)]
+#[path = "common/mod.rs"]
+mod common;
+
+use common::at;
+use std::time::Duration;
+use tictactoe::ai::Evaluation;
+use tictactoe::{Analysis, GameRecord, Move, Outcome, Player, VariationTree};
+
+const GAME: &str = "seed 1\nboard 3x3x3\nrules classic\nmove X 1 1\nmove 0 0 0\nmove X 2 2\n";
+
+#[test]
+fn variation_tree() {
+    let main = vec![
+        Move::place(at(1, 1), Player::Cross),
+        Move::place(at(0, 0), Player::Zero),
+    ];
+    let mut tree = VariationTree::new();
+    let end = tree.add_line(&main);
+    let variation = tree.add(
+        tree.node(end).parent.unwrap(),
+        Move::place(at(2, 2), Player::Zero),
+    );
+    assert_eq!(tree.add_line(&main), end);
+    assert_eq!(tree.main_line(), main);
+    assert_eq!(tree.siblings(variation), vec![end, variation]);
+    assert_eq!(
+        tree.variations(),
+        vec![vec![main[0], Move::place(at(2, 2), Player::Zero)]]
+    );
+}
+
+#[test]
+fn trying_moves_keeps_the_main_line() {
+    let record: GameRecord = GAME.parse().unwrap();
+    let mut analysis = Analysis::new(record.clone()).unwrap();
+    analysis.forward();
+    analysis.forward();
+    analysis.back();
+    assert!(analysis.play(at(1, 1)).is_err());
+    let variation = analysis.play(at(2, 0)).unwrap();
+    assert_eq!(analysis.current(), variation);
+    assert_eq!(analysis.position(variation).unwrap().to_move, Player::Cross);
+    analysis.switch_variation(1);
+    assert_eq!(analysis.tree.line(analysis.current()), record.moves[..2]);
+
+    let saved = analysis.to_record();
+    assert_eq!(saved.moves, record.moves);
+    assert_eq!(saved.variations.len(), 1);
+    assert_eq!(saved.to_string().parse::<GameRecord>().unwrap(), saved);
+    assert_eq!(Analysis::new(saved).unwrap().tree, analysis.tree);
+}
+
+#[test]
+fn game_over_and_evaluation() {
+    let record: GameRecord =
+        "seed 1\nboard 3x3x3\nmove X 0 0\nmove 0 0 1\nmove X 1 0\nmove 0 1 1\n"
+            .parse()
+            .unwrap();
+    let mut analysis = Analysis::new(record).unwrap();
+    analysis.think_time = Duration::from_millis(200);
+    let end = analysis.tree.last(VariationTree::ROOT);
+    analysis.evaluate(end).unwrap();
+    let (evaluation, best) = analysis.tree.node(end).evaluation.unwrap();
+    assert_eq!(evaluation, Evaluation::Win(1));
+    assert_eq!(best.unwrap().position, at(2, 0));
+
+    analysis.go_to(end);
+    let won = analysis.play(at(2, 0)).unwrap();
+    assert_eq!(
+        analysis.position(won).unwrap().outcome,
+        Some(Outcome::Win(Player::Cross))
+    );
+    assert!(analysis.play(at(2, 2)).is_err());
+}
+
+fn analysis_of(rules: &str, moves: &[&str]) -> Analysis {
+    let record: GameRecord = format!("seed 1\nboard 3x3x3\nrules {}\n", rules)
+        .parse()
+        .unwrap();
+    let mut analysis = Analysis::new(record).unwrap();
+    for position in moves {
+        analysis.play(position.parse().unwrap()).unwrap();
+    }
+    analysis
+}
+
+#[test]
+fn vanishing_moves_lift_the_oldest_mark() {
+    let mut analysis = analysis_of("vanishing:2", &["a1", "b1", "a2", "b2"]);
+    let node = analysis.play(at(2, 2)).unwrap();
+    let next = analysis.tree.node(node).next.unwrap();
+    assert_eq!(next.from, Some(at(0, 0)));
+    let position = analysis.position(node).unwrap();
+    assert_eq!(position.board.get(&at(0, 0)), None);
+    assert_eq!(position.board.get(&at(2, 2)), Some(&Player::Cross));
+}
+
+#[test]
+fn sliding_moves_pick_up_a_piece_first() {
+    let mut analysis = analysis_of("sliding:2:anywhere", &["a1", "b1", "a2", "b2"]);
+    let before = analysis.current();
+    assert!(analysis.play(at(2, 2)).is_err());
+    assert!(analysis.play(at(1, 0)).is_err());
+    assert_eq!(analysis.play(at(0, 1)).unwrap(), before);
+    assert_eq!(analysis.selected(), Some(at(0, 1)));
+    let node = analysis.play(at(2, 2)).unwrap();
+    assert_ne!(node, before);
+    assert_eq!(
+        analysis.tree.node(node).next,
+        Some(Move {
+            position: at(2, 2),
+            symbol: Player::Cross,
+            from: Some(at(0, 1)),
+        })
+    );
+    assert_eq!(analysis.selected(), None);
+    assert_eq!(analysis.position(node).unwrap().to_move, Player::Zero);
+}
+
+#[test]
+fn wild_moves_place_either_symbol() {
+    let mut analysis = analysis_of("wild", &["b2"]);
+    // Zeros place a cross, as the symbol has not been switched.
+    let node = analysis.play(at(0, 0)).unwrap();
+    assert_eq!(
+        analysis.tree.node(node).next,
+        Some(Move::place(at(0, 0), Player::Cross))
+    );
+    assert_eq!(
+        analysis.position(node).unwrap().last_mover,
+        Some(Player::Zero)
+    );
+    analysis.toggle_symbol();
+    let node = analysis.play(at(2, 2)).unwrap();
+    assert_eq!(
+        analysis.tree.node(node).next,
+        Some(Move::place(at(2, 2), Player::Zero))
+    );
+    let position = analysis.position(node).unwrap();
+    assert_eq!(position.last_mover, Some(Player::Cross));
+    assert_eq!(position.board.get(&at(2, 2)), Some(&Player::Zero));
+}
+
+#[test]
+fn swapped_moves_count_for_their_symbol() {
+    let record: GameRecord = "seed 1\nboard 3x3x3\nswap pie\nsides swapped\n\
+                              move X b2\nmove X a1\nmove 0 c1\nmove X a2\nmove 0 c2\nmove X a3\n"
+        .parse()
+        .unwrap();
+    let mut analysis = Analysis::new(record).unwrap();
+    analysis.forward();
+    let opening = analysis.position(analysis.current()).unwrap();
+    assert_eq!(opening.last_mover, Some(Player::Cross));
+    assert_eq!(opening.to_move, Player::Cross);
+    analysis.forward();
+    assert_eq!(
+        analysis.position(analysis.current()).unwrap().to_move,
+        Player::Zero
+    );
+    let node = analysis.play(at(1, 0)).unwrap();
+    assert_eq!(
+        analysis.tree.node(node).next,
+        Some(Move::place(at(1, 0), Player::Zero))
+    );
+    let end = analysis
+        .position(analysis.tree.last(VariationTree::ROOT))
+        .unwrap();
+    assert_eq!(end.last_mover, Some(Player::Cross));
+    assert_eq!(end.outcome, Some(Outcome::Win(Player::Cross)));
+}
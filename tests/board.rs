@@ -0,0 +1,387 @@
+use std::io::Result;
+use tictactoe::{Board, Coordinates, Direction, Move, Player, Side, Symmetry};
+
+#[test]
+fn mark_and_overwrites() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    assert!(board
+        .mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)
+        .is_ok());
+    assert!(board
+        .mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)
+        .is_err());
+    assert!(board
+        .mark(Coordinates { x: 3, y: 0, z: 0 }, Player::Zero)
+        .is_err());
+    assert_eq!(
+        board.get(&Coordinates { x: 1, y: 1, z: 0 }),
+        Some(&Player::Cross)
+    );
+    Ok(())
+}
+
+#[test]
+fn symmetry_apply() {
+    let corner = Coordinates { x: 0, y: 0, z: 0 };
+    assert_eq!(
+        Symmetry::Rotate90.apply(corner, 3, 3),
+        Coordinates { x: 2, y: 0, z: 0 }
+    );
+    assert_eq!(
+        Symmetry::Rotate180.apply(corner, 3, 3),
+        Coordinates { x: 2, y: 2, z: 0 }
+    );
+    assert_eq!(
+        Symmetry::Rotate270.apply(corner, 3, 3),
+        Coordinates { x: 0, y: 2, z: 0 }
+    );
+    assert_eq!(
+        Symmetry::FlipAntiDiagonal.apply(corner, 3, 3),
+        Coordinates { x: 2, y: 2, z: 0 }
+    );
+}
+
+#[test]
+fn symmetry_inverse() {
+    let position = Coordinates { x: 1, y: 3, z: 0 };
+    for symmetry in Symmetry::ALL.iter() {
+        let transformed = symmetry.apply(position, 4, 4);
+        assert_eq!(symmetry.inverse().apply(transformed, 4, 4), position);
+    }
+}
+
+#[test]
+fn zobrist_hash_is_incremental() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    assert_eq!(board.zobrist_hash(), 0);
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    let hash = board.zobrist_hash();
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    assert_ne!(board.zobrist_hash(), hash);
+    board.unmark(&Coordinates { x: 1, y: 1, z: 0 });
+    assert_eq!(board.zobrist_hash(), hash);
+    Ok(())
+}
+
+#[test]
+fn zobrist_hash_ignores_move_order() -> Result<()> {
+    let mut first = Board::from(Side(3));
+    first.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    first.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Zero)?;
+    let mut second = Board::from(Side(3));
+    second.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Zero)?;
+    second.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    assert_eq!(first.zobrist_hash(), second.zobrist_hash());
+    Ok(())
+}
+
+#[test]
+fn canonical_hash_of_symmetric_positions() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Zero)?;
+    for symmetry in Symmetry::ALL.iter() {
+        let transformed = board.transformed(*symmetry);
+        assert_eq!(transformed.canonical_hash(), board.canonical_hash());
+    }
+    Ok(())
+}
+
+#[test]
+fn canonical_maps_back() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 2, y: 2, z: 0 }, Player::Cross)?;
+    let (canonical, symmetry) = board.canonical();
+    assert_eq!(canonical.zobrist_hash(), board.canonical_hash());
+    let (position, _) = canonical.marked_positions().iter().next().unwrap();
+    assert_eq!(
+        symmetry
+            .inverse()
+            .apply(*position, board.width, board.height),
+        Coordinates { x: 2, y: 2, z: 0 }
+    );
+    Ok(())
+}
+
+#[test]
+fn rectangular_board_symmetries() -> Result<()> {
+    let mut board = Board::new(4, 3, 3);
+    assert_eq!(board.symmetries().len(), 4);
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    let mut mirrored = Board::new(4, 3, 3);
+    mirrored.mark(Coordinates { x: 3, y: 2, z: 0 }, Player::Cross)?;
+    assert_eq!(board.canonical_hash(), mirrored.canonical_hash());
+    Ok(())
+}
+
+#[test]
+fn completes_line() -> Result<()> {
+    let mut board = Board::new(5, 5, 3);
+    board.mark(Coordinates { x: 1, y: 3, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 2, z: 0 }, Player::Cross)?;
+    assert!(!board.has_line(&Player::Cross));
+    board.mark(Coordinates { x: 3, y: 1, z: 0 }, Player::Cross)?;
+    assert!(board.completes_line(&Coordinates { x: 2, y: 2, z: 0 }, &Player::Cross));
+    assert!(board.has_line(&Player::Cross));
+    assert!(!board.has_line(&Player::Zero));
+    Ok(())
+}
+
+#[test]
+fn completes_line_needs_win_length() -> Result<()> {
+    let mut board = Board::new(5, 5, 4);
+    for x in 0..3 {
+        board.mark(Coordinates { x, y: 0, z: 0 }, Player::Zero)?;
+    }
+    board.mark(Coordinates { x: 3, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 4, y: 0, z: 0 }, Player::Zero)?;
+    assert!(!board.has_line(&Player::Zero));
+    Ok(())
+}
+
+#[test]
+fn lines() {
+    assert_eq!(Board::from(Side(3)).lines().len(), 8);
+    assert_eq!(Board::new(4, 3, 3).lines().len(), 14);
+    assert_eq!(Board::layered(4, 4, 4, 4).lines().len(), 76);
+}
+
+#[test]
+fn torus_lines_wrap_around() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    board.torus = true;
+    assert_eq!(board.lines().len(), 12);
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 2, y: 1, z: 0 }, Player::Cross)?;
+    assert!(!board.has_line(&Player::Cross));
+    // The broken diagonal carries on from the left edge.
+    board.mark(Coordinates { x: 0, y: 2, z: 0 }, Player::Cross)?;
+    assert!(board.completes_line(&Coordinates { x: 2, y: 1, z: 0 }, &Player::Cross));
+    assert_eq!(
+        board.wrap(Coordinates { x: -1, y: 3, z: 0 }),
+        Coordinates { x: 2, y: 0, z: 0 }
+    );
+    Ok(())
+}
+
+#[test]
+fn torus_does_not_count_marks_twice() -> Result<()> {
+    let mut board = Board::new(3, 3, 4);
+    board.torus = true;
+    assert!(board.lines().is_empty());
+    for x in 0..3 {
+        board.mark(Coordinates { x, y: 1, z: 0 }, Player::Zero)?;
+    }
+    assert!(!board.has_line(&Player::Zero));
+    let mut board = Board::new(4, 4, 3);
+    board.torus = true;
+    assert_eq!(board.lines().len(), 64);
+    Ok(())
+}
+
+#[test]
+fn board_from_map() -> Result<()> {
+    let mut board = Board::from_map("win 3\n##.##\n##.##\n.....\n##.##\n##.##\n")?;
+    assert_eq!((board.width, board.height, board.win_length), (5, 5, 3));
+    assert_eq!(board.size(), 9);
+    assert_eq!(board.positions().len(), 9);
+    assert_eq!(board.lines().len(), 6);
+    assert_eq!(board.symmetries().len(), 8);
+    assert!(board.is_blocked(&Coordinates { x: 0, y: 0, z: 0 }));
+    assert!(board
+        .mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)
+        .is_err());
+    for x in 1..4 {
+        board.mark(Coordinates { x, y: 2, z: 0 }, Player::Cross)?;
+    }
+    assert!(board.has_line(&Player::Cross));
+    // Short rows are blocked past their end.
+    let board = Board::from_map(".\n...\n")?;
+    assert_eq!((board.width, board.height, board.win_length), (3, 2, 2));
+    assert_eq!(board.blocked_positions().len(), 2);
+    assert!(Board::from_map("..\n.x\n").is_err());
+    assert!(Board::from_map("win 4\n...\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn holes_break_symmetry_and_gravity() -> Result<()> {
+    let mut board = Board::from_map("#..\n...\n...\n")?;
+    assert_eq!(
+        board.symmetries(),
+        vec![Symmetry::Identity, Symmetry::FlipDiagonal]
+    );
+    let mut board_with_gravity = Board::from_map("...\n.#.\n...\n")?;
+    board_with_gravity.gravity = true;
+    assert_eq!(
+        board_with_gravity.legal_moves(),
+        vec![
+            Coordinates { x: 0, y: 2, z: 0 },
+            Coordinates { x: 1, y: 0, z: 0 },
+            Coordinates { x: 2, y: 2, z: 0 },
+        ]
+    );
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Zero)?;
+    let (canonical, symmetry) = board.canonical();
+    assert_eq!(canonical.blocked_positions(), board.blocked_positions());
+    assert_eq!(canonical.transformed(symmetry.inverse()), board);
+    Ok(())
+}
+
+#[test]
+fn completes_line_through_layers() -> Result<()> {
+    let mut board = Board::layered(4, 4, 4, 4);
+    assert!(!board.contains(&Coordinates { x: 0, y: 0, z: 4 }));
+    for z in 0..3 {
+        board.mark(Coordinates { x: z, y: 3 - z, z }, Player::Cross)?;
+    }
+    assert!(!board.has_line(&Player::Cross));
+    board.mark(Coordinates { x: 3, y: 0, z: 3 }, Player::Cross)?;
+    assert!(board.completes_line(&Coordinates { x: 3, y: 0, z: 3 }, &Player::Cross));
+    assert_eq!(board.empty_positions().len(), 60);
+    Ok(())
+}
+
+#[test]
+fn gravity_drops_marks() -> Result<()> {
+    let mut board = Board::new(7, 6, 4);
+    board.gravity = true;
+    assert_eq!(board.legal_moves().len(), 7);
+    assert!(board
+        .mark(Coordinates { x: 3, y: 0, z: 0 }, Player::Cross)
+        .is_err());
+    let bottom = Coordinates { x: 3, y: 5, z: 0 };
+    assert_eq!(
+        board.drop_position(&Coordinates { x: 3, y: 0, z: 0 }),
+        Some(bottom)
+    );
+    board.mark(bottom, Player::Cross)?;
+    assert_eq!(
+        board.drop_position(&Coordinates { x: 3, y: 0, z: 0 }),
+        Some(Coordinates { x: 3, y: 4, z: 0 })
+    );
+    for y in 0..5 {
+        board.mark(
+            Coordinates {
+                x: 3,
+                y: 4 - y,
+                z: 0,
+            },
+            Player::Zero,
+        )?;
+    }
+    assert_eq!(board.drop_position(&bottom), None);
+    assert_eq!(board.legal_moves().len(), 6);
+    Ok(())
+}
+
+#[test]
+fn play_and_undo_slides() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    let from = Coordinates { x: 0, y: 0, z: 0 };
+    board.mark(from, Player::Cross)?;
+    let hash = board.zobrist_hash();
+    let slide = Move {
+        position: Coordinates { x: 1, y: 1, z: 0 },
+        symbol: Player::Cross,
+        from: Some(from),
+    };
+    let undo = board.play(&slide)?;
+    assert_eq!(board.get(&from), None);
+    assert_eq!(board.get(&slide.position), Some(&Player::Cross));
+    board.undo(&undo);
+    assert_eq!(board.get(&from), Some(&Player::Cross));
+    assert_eq!(board.zobrist_hash(), hash);
+    let taken = Move {
+        symbol: Player::Zero,
+        ..slide
+    };
+    assert!(board.play(&taken).is_err());
+    Ok(())
+}
+
+#[test]
+fn marks_keep_their_order() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    let first = Coordinates { x: 2, y: 2, z: 0 };
+    let second = Coordinates { x: 0, y: 0, z: 0 };
+    board.mark(first, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(second, Player::Cross)?;
+    assert_eq!(board.marks_of(Player::Cross), vec![first, second]);
+    let vanishing = Move {
+        position: Coordinates { x: 2, y: 0, z: 0 },
+        symbol: Player::Cross,
+        from: Some(first),
+    };
+    let undo = board.play(&vanishing)?;
+    assert_eq!(
+        board.marks_of(Player::Cross),
+        vec![second, vanishing.position]
+    );
+    board.undo(&undo);
+    assert_eq!(board.marks_of(Player::Cross), vec![first, second]);
+    Ok(())
+}
+
+#[test]
+fn undo_restores_a_slid_piece_in_its_place() -> Result<()> {
+    let mut board = Board::from(Side(3));
+    let first = Coordinates { x: 2, y: 2, z: 0 };
+    let second = Coordinates { x: 0, y: 0, z: 0 };
+    let third = Coordinates { x: 1, y: 0, z: 0 };
+    for position in [first, second, third] {
+        board.mark(position, Player::Cross)?;
+    }
+    let slide = Move {
+        position: Coordinates { x: 1, y: 1, z: 0 },
+        symbol: Player::Cross,
+        from: Some(second),
+    };
+    let undo = board.play(&slide)?;
+    assert_eq!(
+        board.marks_of(Player::Cross),
+        vec![first, third, slide.position]
+    );
+    board.undo(&undo);
+    assert_eq!(board.marks_of(Player::Cross), vec![first, second, third]);
+    Ok(())
+}
+
+#[test]
+fn swap_sides() -> Result<()> {
+    let first = Coordinates { x: 1, y: 1, z: 0 };
+    let second = Coordinates { x: 0, y: 2, z: 0 };
+    let mut board = Board::from(Side(3));
+    board.mark(first, Player::Cross)?;
+    board.mark(second, Player::Zero)?;
+    board.mark(Coordinates { x: 2, y: 0, z: 0 }, Player::Cross)?;
+    board.swap_sides();
+    let mut expected = Board::from(Side(3));
+    expected.mark(first, Player::Zero)?;
+    expected.mark(second, Player::Cross)?;
+    expected.mark(Coordinates { x: 2, y: 0, z: 0 }, Player::Zero)?;
+    assert_eq!(board, expected);
+    assert_eq!(board.zobrist_hash(), expected.zobrist_hash());
+    assert_eq!(board.marks_of(Player::Zero)[0], first);
+    Ok(())
+}
+
+#[test]
+fn cursor_skips_blocked_boxes() -> Result<()> {
+    let at = |x, y| Coordinates { x, y, z: 0 };
+    let mut board = Board::from_map("win 3\n.#..\n##.#\n#...\n")?;
+    // The cursor jumps over blocked boxes and stops at the edges.
+    assert_eq!(board.cursor_step(at(0, 0), &Direction::Right), at(2, 0));
+    assert_eq!(board.cursor_step(at(3, 0), &Direction::Right), at(3, 0));
+    assert_eq!(board.cursor_step(at(2, 2), &Direction::Up), at(2, 1));
+    // It stays put when only blocked boxes lie ahead.
+    assert_eq!(board.cursor_step(at(0, 0), &Direction::Down), at(0, 0));
+    assert_eq!(board.cursor_step(at(0, 0), &Direction::NextLayer), at(0, 0));
+    // On a torus it comes back in at the opposite edge.
+    board.torus = true;
+    assert_eq!(board.cursor_step(at(3, 0), &Direction::Right), at(0, 0));
+    assert_eq!(board.cursor_step(at(0, 0), &Direction::Left), at(3, 0));
+    assert_eq!(board.cursor_step(at(3, 2), &Direction::Up), at(3, 0));
+    Ok(())
+}
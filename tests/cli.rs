@@ -0,0 +1,309 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use tictactoe::cli::{parse_board_config, Command, GameOptions, Mode};
+use tictactoe::{Seat, Swap, TimeControl, Variant, AI};
+
+fn parse(args: &[&str]) -> std::io::Result<Command> {
+    Command::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn parse_play() {
+    assert_eq!(parse(&[]).unwrap(), Command::Play(GameOptions::default()));
+}
+
+#[test]
+fn parse_play_options() {
+    assert_eq!(
+        parse(&["--seed", "42", "--save", "game.txt"]).unwrap(),
+        Command::Play(GameOptions {
+            seed: Some(42),
+            save: Some(PathBuf::from("game.txt")),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--seed"]).is_err());
+    assert!(parse(&["--seed", "forty-two"]).is_err());
+}
+
+#[test]
+fn parse_timing_options() {
+    assert_eq!(
+        parse(&["--ai", "minimax", "--ai-time", "250", "--clock", "60+2"]).unwrap(),
+        Command::Play(GameOptions {
+            ai: AI::MiniMax,
+            ai_time: Some(Duration::from_millis(250)),
+            clock: Some(TimeControl {
+                base: Duration::from_secs(60),
+                increment: Duration::from_secs(2),
+            }),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--clock", "+2"]).is_err());
+}
+
+#[test]
+fn parse_rules() {
+    assert_eq!(
+        parse(&["--rules", "misere"]).unwrap(),
+        Command::Play(GameOptions {
+            rules: Variant::Misere,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--rules", "order-and-chaos"]).unwrap(),
+        Command::Play(GameOptions {
+            rules: Variant::OrderAndChaos,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--rules", "chess"]).is_err());
+}
+
+#[test]
+fn parse_solve() {
+    assert_eq!(
+        parse(&["solve", "4x4x3"]).unwrap(),
+        Command::Solve {
+            width: 4,
+            height: 4,
+            win_length: 3,
+        }
+    );
+    assert!(parse(&["solve"]).is_err());
+    assert!(parse(&["bogus"]).is_err());
+}
+
+#[test]
+fn board_config() {
+    assert_eq!(parse_board_config("3x3x3").unwrap(), (3, 3, 3));
+    assert_eq!(parse_board_config("7x6").unwrap(), (7, 6, 6));
+    assert!(parse_board_config("3x3x4").is_err());
+    assert!(parse_board_config("0x3x3").is_err());
+    assert!(parse_board_config("3by3").is_err());
+}
+
+#[test]
+fn parse_mode() {
+    assert_eq!(
+        parse(&["--mode", "ultimate", "--ai", "mcts"]).unwrap(),
+        Command::Play(GameOptions {
+            mode: Mode::Ultimate,
+            ai: AI::MonteCarlo,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--mode", "ultimate", "--ai", "minimax"]).is_err());
+    for flags in [
+        &["--rules", "misere"][..],
+        &["--clock", "60"],
+        &["--save", "game.txt"],
+    ] {
+        let args: Vec<&str> = ["--mode", "ultimate", "--ai", "mcts"]
+            .iter()
+            .chain(flags)
+            .copied()
+            .collect();
+        assert!(parse(&args).is_err(), "{:?}", flags);
+    }
+    assert_eq!(
+        parse(&["--mode", "qubic", "--ai", "minimax"]).unwrap(),
+        Command::Play(GameOptions {
+            mode: Mode::Qubic,
+            ai: AI::MiniMax,
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--mode", "quantum"]).unwrap(),
+        Command::Play(GameOptions {
+            mode: Mode::Quantum,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--mode", "quantum", "--ai", "minimax"]).is_err());
+    for flags in [
+        &["--rules", "wild"][..],
+        &["--clock", "5"],
+        &["--save", "q.txt"],
+    ] {
+        let args: Vec<&str> = ["--mode", "quantum"].iter().chain(flags).copied().collect();
+        assert!(parse(&args).is_err(), "{:?}", flags);
+    }
+}
+
+#[test]
+fn parse_board_and_gravity() {
+    assert_eq!(
+        parse(&["--board", "7x6x4", "--gravity"]).unwrap(),
+        Command::Play(GameOptions {
+            board: Some((7, 6, 4)),
+            gravity: true,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--mode", "qubic", "--board", "5x5"]).is_err());
+    assert!(parse(&["--mode", "ultimate", "--gravity"]).is_err());
+    assert_eq!(
+        parse(&["--torus"]).unwrap(),
+        Command::Play(GameOptions {
+            torus: true,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--mode", "quantum", "--torus"]).is_err());
+}
+
+#[test]
+fn parse_map() {
+    assert_eq!(
+        parse(&["--map", "plus.txt"]).unwrap(),
+        Command::Play(GameOptions {
+            map: Some(PathBuf::from("plus.txt")),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--map", "plus.txt", "--board", "5x5"]).is_err());
+    assert!(parse(&["--map", "plus.txt", "--mode", "qubic"]).is_err());
+}
+
+#[test]
+fn parse_swap() {
+    assert_eq!(
+        parse(&["--swap", "swap2", "--ai-opens"]).unwrap(),
+        Command::Play(GameOptions {
+            swap: Some(Swap::Swap2),
+            ai_opens: true,
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--swap", "pie2"]).is_err());
+    assert!(parse(&["--ai-opens"]).is_err());
+    assert!(parse(&["--swap", "pie", "--mode", "ultimate", "--ai", "mcts"]).is_err());
+    assert!(parse(&["--swap", "pie", "--rules", "wild"]).is_err());
+}
+
+#[test]
+fn parse_players() {
+    assert_eq!(
+        parse(&["--players", "human,ai,ai", "--ai", "mcts"]).unwrap(),
+        Command::Play(GameOptions {
+            ai: AI::MonteCarlo,
+            rules: Variant::Multiplayer { players: 3 },
+            seats: Some(vec![Seat::Human, Seat::Ai, Seat::Ai]),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--rules", "multiplayer:4"]).unwrap(),
+        Command::Play(GameOptions {
+            rules: Variant::Multiplayer { players: 4 },
+            seats: Some(vec![Seat::Human, Seat::Ai, Seat::Ai, Seat::Ai]),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        parse(&["--players", "ai,human"]).unwrap(),
+        Command::Play(GameOptions {
+            seats: Some(vec![Seat::Ai, Seat::Human]),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--players", "human"]).is_err());
+    assert!(parse(&["--players", "human,robot"]).is_err());
+    assert!(parse(&["--players", "human,ai,ai", "--ai", "minimax"]).is_err());
+    assert!(parse(&["--players", "human,human,human", "--rules", "misere"]).is_err());
+    assert!(parse(&["--players", "human,ai,ai", "--rules", "multiplayer:4"]).is_err());
+    assert!(parse(&["--players", "ai,human", "--swap", "pie"]).is_err());
+}
+
+#[test]
+fn parse_best_of() {
+    assert_eq!(
+        parse(&["--best-of", "5"]).unwrap(),
+        Command::Play(GameOptions {
+            best_of: Some(5),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["--best-of", "0"]).is_err());
+    assert!(parse(&["--best-of", "3", "--mode", "quantum", "--ai", "mcts"]).is_err());
+}
+
+#[test]
+fn parse_stats() {
+    assert_eq!(
+        parse(&["stats"]).unwrap(),
+        Command::Stats {
+            profile: "default".to_string()
+        }
+    );
+    assert_eq!(
+        parse(&["stats", "--profile", "ada"]).unwrap(),
+        Command::Stats {
+            profile: "ada".to_string()
+        }
+    );
+    assert_eq!(
+        parse(&["--profile", "ada"]).unwrap(),
+        Command::Play(GameOptions {
+            profile: Some("ada".to_string()),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["stats", "--profile", "../ada"]).is_err());
+    assert!(parse(&["stats", "ada"]).is_err());
+}
+
+#[test]
+fn parse_ratings() {
+    assert_eq!(parse(&["leaderboard"]).unwrap(), Command::Leaderboard);
+    assert!(parse(&["leaderboard", "ada"]).is_err());
+    assert_eq!(
+        parse(&["--ai", "auto"]).unwrap(),
+        Command::Play(GameOptions {
+            auto_ai: true,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn parse_replay() {
+    assert_eq!(
+        parse(&["replay", "game.txt", "--speed", "250"]).unwrap(),
+        Command::Replay {
+            file: "game.txt".into(),
+            speed: Duration::from_millis(250),
+        }
+    );
+    assert!(parse(&["replay"]).is_err());
+    assert!(parse(&["replay", "game.txt", "--fast"]).is_err());
+}
+
+#[test]
+fn parse_analyze() {
+    assert_eq!(
+        parse(&["analyze", "game.txt", "--ai-time", "300"]).unwrap(),
+        Command::Analyze {
+            file: "game.txt".into(),
+            think_time: Some(Duration::from_millis(300)),
+        }
+    );
+    assert!(parse(&["analyze"]).is_err());
+}
+
+#[test]
+fn parse_edit() {
+    assert_eq!(
+        parse(&["edit", "--board", "4x4"]).unwrap(),
+        Command::Edit(GameOptions {
+            board: Some((4, 4, 4)),
+            ..Default::default()
+        })
+    );
+    assert!(parse(&["edit", "--swap", "pie"]).is_err());
+    assert!(parse(&["edit", "--players", "human,ai,ai", "--ai", "mcts"]).is_err());
+}
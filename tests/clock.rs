@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+use tictactoe::{Clock, TimeControl};
+
+fn blitz() -> TimeControl {
+    TimeControl {
+        base: Duration::from_secs(60),
+        increment: Duration::from_secs(2),
+    }
+}
+
+#[test]
+fn time_control_from_str() {
+    assert_eq!("60+2".parse::<TimeControl>().unwrap(), blitz());
+    assert_eq!(
+        "30".parse::<TimeControl>().unwrap(),
+        TimeControl {
+            base: Duration::from_secs(30),
+            increment: Duration::from_secs(0),
+        }
+    );
+    assert!("0+5".parse::<TimeControl>().is_err());
+    assert!("1:00".parse::<TimeControl>().is_err());
+}
+
+#[test]
+fn runs_only_while_started() {
+    let now = Instant::now();
+    let mut clock = Clock::from(blitz());
+    assert_eq!(clock.remaining(now + Duration::from_secs(5)), blitz().base);
+    clock.start(now);
+    assert!(clock.is_running());
+    assert_eq!(
+        clock.remaining(now + Duration::from_secs(5)),
+        Duration::from_secs(55)
+    );
+}
+
+#[test]
+fn stop_adds_increment() {
+    let now = Instant::now();
+    let mut clock = Clock::from(blitz());
+    clock.start(now);
+    clock.stop(now + Duration::from_secs(10));
+    assert!(!clock.is_running());
+    assert_eq!(clock.remaining(now), Duration::from_secs(52));
+}
+
+#[test]
+fn flag() {
+    let now = Instant::now();
+    let mut clock = Clock::from(blitz());
+    clock.start(now);
+    assert!(!clock.is_flagged(now + Duration::from_secs(59)));
+    clock.stop(now + Duration::from_secs(61));
+    // No increment is given once the player has run out of time.
+    assert!(clock.is_flagged(now + Duration::from_secs(61)));
+}
+
+#[test]
+fn display() {
+    let now = Instant::now();
+    let mut clock = Clock::from(blitz());
+    assert_eq!(clock.display(now), "1:00");
+    clock.start(now);
+    assert_eq!(clock.display(now + Duration::from_millis(51_500)), "0:08.5");
+}
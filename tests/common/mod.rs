@@ -0,0 +1,6 @@
+use tictactoe::Coordinates;
+
+/// The box at the given column and row of the first layer.
+pub fn at(x: i16, y: i16) -> Coordinates {
+    Coordinates { x, y, z: 0 }
+}
@@ -1,17 +1,23 @@
+// Each test file also builds on its own, so the helpers are loaded per file.
+#[allow(clippy::duplicate_mod)]
+#[path = "common/mod.rs"]
+mod common;
+
+use common::at;
 use tictactoe::{Coordinates, Direction};
 
 #[test]
 fn add_operator() {
-    let c1 = Coordinates { x: 5, y: 10 };
-    let c2 = Coordinates { x: 20, y: 30 };
-    assert_eq!(c1 + c2, Coordinates { x: 25, y: 40 });
+    let c1 = Coordinates { x: 5, y: 10, z: 0 };
+    let c2 = Coordinates { x: 20, y: 30, z: 0 };
+    assert_eq!(c1 + c2, Coordinates { x: 25, y: 40, z: 0 });
 }
 
 #[test]
 fn direction_relative_coords_up() {
     assert_eq!(
         Direction::Up.get_relative_coords(),
-        Coordinates { x: 0, y: -1 }
+        Coordinates { x: 0, y: -1, z: 0 }
     );
 }
 
@@ -19,7 +25,7 @@ fn direction_relative_coords_up() {
 fn direction_relative_coords_down() {
     assert_eq!(
         Direction::Down.get_relative_coords(),
-        Coordinates { x: 0, y: 1 }
+        Coordinates { x: 0, y: 1, z: 0 }
     );
 }
 
@@ -27,7 +33,7 @@ fn direction_relative_coords_down() {
 fn direction_relative_coords_left() {
     assert_eq!(
         Direction::Left.get_relative_coords(),
-        Coordinates { x: -1, y: 0 }
+        Coordinates { x: -1, y: 0, z: 0 }
     );
 }
 
@@ -35,6 +41,46 @@ fn direction_relative_coords_left() {
 fn direction_relative_coords_right() {
     assert_eq!(
         Direction::Right.get_relative_coords(),
-        Coordinates { x: 1, y: 0 }
+        Coordinates { x: 1, y: 0, z: 0 }
+    );
+}
+
+#[test]
+fn direction_relative_coords_layers() {
+    assert_eq!(
+        Direction::PreviousLayer.get_relative_coords(),
+        Coordinates { x: 0, y: 0, z: -1 }
+    );
+    assert_eq!(
+        Direction::NextLayer.get_relative_coords(),
+        Coordinates { x: 0, y: 0, z: 1 }
+    );
+}
+
+#[test]
+fn algebraic_notation() {
+    assert_eq!(at(0, 0).to_string(), "a1");
+    assert_eq!(at(1, 2).to_string(), "b3");
+    assert_eq!(at(25, 9).to_string(), "z10");
+    assert_eq!(at(26, 0).to_string(), "aa1");
+    assert_eq!(Coordinates { z: 3, ..at(2, 1) }.to_string(), "c2/4");
+}
+
+#[test]
+fn parse_algebraic_notation() {
+    assert_eq!("b3".parse::<Coordinates>().unwrap(), at(1, 2));
+    assert_eq!("B3".parse::<Coordinates>().unwrap(), at(1, 2));
+    assert_eq!(
+        "ab12/2".parse::<Coordinates>().unwrap(),
+        Coordinates { z: 1, ..at(27, 11) }
     );
+    for position in [at(0, 0), at(51, 3), Coordinates { z: 2, ..at(702, 7) }] {
+        assert_eq!(
+            position.to_string().parse::<Coordinates>().unwrap(),
+            position
+        );
+    }
+    for invalid in ["", "b", "3", "b0", "b+3", "3b", "b3/0", "b3/", "b-1", "é1"] {
+        assert!(invalid.parse::<Coordinates>().is_err(), "{}", invalid);
+    }
 }
@@ -0,0 +1,88 @@
+// Each test file also builds on its own, so the helpers are loaded per file.
+#[allow(clippy::duplicate_mod)]
+#[path = "common/mod.rs"]
+mod common;
+
+use common::at;
+use tictactoe::editor::validate;
+use tictactoe::{Analysis, Board, Editor, GameRecord, Player, Side, Variant};
+
+#[test]
+fn setting_up_marks() {
+    let mut editor = Editor::new(Board::from(Side(3)));
+    editor.set(at(1, 1), Some(Player::Zero)).unwrap();
+    editor.set(at(1, 1), Some(Player::Cross)).unwrap();
+    assert_eq!(editor.board.get(&at(1, 1)), Some(&Player::Cross));
+    editor.set(at(1, 1), None).unwrap();
+    assert!(editor.board.marked_positions().is_empty());
+    assert!(editor.set(at(3, 3), Some(Player::Cross)).is_err());
+}
+
+#[test]
+fn validation() {
+    let rules = Variant::Classic;
+    let mut board = Board::from(Side(3));
+    assert!(validate(&board, Player::Cross, &rules).is_ok());
+    assert!(validate(&board, Player::Zero, &rules).is_err());
+    board.mark(at(0, 0), Player::Cross).unwrap();
+    assert!(validate(&board, Player::Zero, &rules).is_ok());
+    assert!(validate(&board, Player::Cross, &rules).is_err());
+    board.mark(at(1, 0), Player::Cross).unwrap();
+    assert!(validate(&board, Player::Zero, &rules).is_err());
+    assert!(validate(&board, Player::Zero, &Variant::OrderAndChaos).is_ok());
+
+    for x in 0..3 {
+        board.unmark(&at(x, 0));
+        board.mark(at(x, 0), Player::Cross).unwrap();
+        board.mark(at(x, 2), Player::Zero).unwrap();
+    }
+    let error = validate(&board, Player::Cross, &rules).unwrap_err();
+    assert_eq!(error.to_string(), "both players have a line");
+}
+
+#[test]
+fn piece_limit() {
+    let rules: Variant = "sliding:2:anywhere".parse().unwrap();
+    let mut board = Board::from(Side(3));
+    for (position, player) in [
+        (at(0, 0), Player::Cross),
+        (at(1, 0), Player::Zero),
+        (at(0, 1), Player::Cross),
+        (at(1, 1), Player::Zero),
+    ] {
+        board.mark(position, player).unwrap();
+    }
+    assert!(validate(&board, Player::Cross, &rules).is_ok());
+    board.mark(at(2, 2), Player::Cross).unwrap();
+    let error = validate(&board, Player::Zero, &rules).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "players can have no more than 2 marks on the board"
+    );
+    assert!(validate(&board, Player::Zero, &Variant::Classic).is_ok());
+}
+
+#[test]
+fn floating_marks() {
+    let mut board = Board::from(Side(3));
+    board.gravity = true;
+    board.mark(at(0, 2), Player::Cross).unwrap();
+    board.mark(at(0, 1), Player::Zero).unwrap();
+    assert!(validate(&board, Player::Cross, &Variant::Classic).is_ok());
+    board.unmark(&at(0, 2));
+    board.mark(at(1, 2), Player::Cross).unwrap();
+    assert!(validate(&board, Player::Cross, &Variant::Classic).is_err());
+}
+
+#[test]
+fn analyzing_a_set_up_position() {
+    let mut board = Board::from(Side(3));
+    board.mark(at(1, 1), Player::Cross).unwrap();
+    let record = GameRecord::from_position(&board, Player::Zero, 1);
+    let record: GameRecord = record.to_string().parse().unwrap();
+    assert_eq!(record.board(), board);
+    let analysis = Analysis::new(record).unwrap();
+    let position = analysis.position(analysis.current()).unwrap();
+    assert_eq!(position.to_move, Player::Zero);
+    assert_eq!(position.board, board);
+}
@@ -1,6 +1,11 @@
 use crossterm::Result;
 use std::collections::HashMap;
-use tictactoe::{Coordinates, Grid, Player, Side, TicTacToe, AI};
+use tictactoe::ai::Evaluation;
+use tictactoe::rules::Movement;
+use tictactoe::{
+    Board, Coordinates, GameCommand, GameRecord, Grid, InputEvent, Move, Outcome, Player, Side,
+    Swap, TicTacToe, Variant, AI,
+};
 
 fn tictactoe_from_settings() -> Result<TicTacToe> {
     TicTacToe::from(Grid::from(Side(3)), AI::Random)
@@ -9,29 +14,33 @@ fn tictactoe_from_settings() -> Result<TicTacToe> {
 #[test]
 fn settings() -> Result<()> {
     let tictactoe = tictactoe_from_settings()?;
-    assert_eq!(tictactoe.cursor, Coordinates { x: 0, y: 0 });
+    assert_eq!(tictactoe.cursor, Coordinates { x: 0, y: 0, z: 0 });
     assert_eq!(tictactoe.grid, Grid::from(Side(3)));
     assert_eq!(tictactoe.ai_algo, AI::Random);
-    assert_eq!(tictactoe.marked_positions, HashMap::new());
+    assert_eq!(tictactoe.board, Board::from(Side(3)));
     Ok(())
 }
 
 #[test]
 fn set_cursor_to_grid() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    let grid_coords = Coordinates { x: 2, y: 1 };
+    let grid_coords = Coordinates { x: 2, y: 1, z: 0 };
     tictactoe.set_cursor_to_grid(&grid_coords)?;
     assert_eq!(tictactoe.cursor, grid_coords);
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn mark_and_overwrites() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    let grid_coords = Coordinates { x: 1, y: 1 };
+    let grid_coords = Coordinates { x: 1, y: 1, z: 0 };
     tictactoe.set_cursor_to_grid(&grid_coords)?;
     assert!(tictactoe.mark_cross().is_ok());
     // Marking again at the same grid coordinates should error out.
@@ -39,58 +48,70 @@ fn mark_and_overwrites() -> Result<()> {
     assert!(tictactoe.mark_zero().is_err());
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn marked_positions() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 1, y: 1 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 1, y: 1, z: 0 })?;
     tictactoe.mark_cross()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 0 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 0, z: 0 })?;
     tictactoe.mark_zero()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 0, y: 0 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 0, y: 0, z: 0 })?;
     tictactoe.mark_cross()?;
     let marked_positions: HashMap<Coordinates, Player> = [
-        (Coordinates { x: 1, y: 1 }, Player::Cross),
-        (Coordinates { x: 2, y: 0 }, Player::Zero),
-        (Coordinates { x: 0, y: 0 }, Player::Cross),
+        (Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        (Coordinates { x: 2, y: 0, z: 0 }, Player::Zero),
+        (Coordinates { x: 0, y: 0, z: 0 }, Player::Cross),
     ]
     .iter()
     .cloned()
     .collect();
-    assert_eq!(tictactoe.marked_positions, marked_positions);
+    assert_eq!(tictactoe.board.marked_positions(), &marked_positions);
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn grid_has_empty_boxes() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 2 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 2, z: 0 })?;
     tictactoe.mark_cross()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 1 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 1, z: 0 })?;
     tictactoe.mark_zero()?;
-    tictactoe.set_cursor_to_grid(&Coordinates { x: 1, y: 0 })?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 1, y: 0, z: 0 })?;
     tictactoe.mark_cross()?;
     assert!(tictactoe.grid_has_empty_boxes());
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn grid_does_not_has_empty_boxes() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    let Side(side) = tictactoe.grid.side;
+    let side = tictactoe.grid.width;
     let mut iteration = 0;
     for x in 0..(side as i16) {
         for y in 0..(side as i16) {
-            tictactoe.set_cursor_to_grid(&Coordinates { x, y })?;
+            tictactoe.set_cursor_to_grid(&Coordinates { x, y, z: 0 })?;
             if iteration % 2 == 0 {
                 tictactoe.mark_cross()?;
             } else {
@@ -102,36 +123,263 @@ fn grid_does_not_has_empty_boxes() -> Result<()> {
     assert!(!tictactoe.grid_has_empty_boxes());
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn check_for_victory() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    let Side(side) = tictactoe.grid.side;
+    let side = tictactoe.grid.width;
     for z in 0..(side as i16) {
-        tictactoe.set_cursor_to_grid(&Coordinates { x: z, y: z })?;
+        tictactoe.set_cursor_to_grid(&Coordinates { x: z, y: z, z: 0 })?;
         tictactoe.mark_cross()?;
     }
     assert!(tictactoe.check_for_victory(&Player::Cross));
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
     Ok(())
 }
 
 #[test]
 fn check_for_not_victory() -> Result<()> {
     let mut tictactoe = tictactoe_from_settings()?;
-    let Side(side) = tictactoe.grid.side;
+    let side = tictactoe.grid.width;
     for z in 0..(side as i16 - 1) {
-        tictactoe.set_cursor_to_grid(&Coordinates { x: z, y: z })?;
+        tictactoe.set_cursor_to_grid(&Coordinates { x: z, y: z, z: 0 })?;
         tictactoe.mark_cross()?;
     }
     assert!(!tictactoe.check_for_victory(&Player::Cross));
     // Move the cursor as far in the bottom right corner as possible.
     // Otherwise the terminal test output gets messed up.
-    TicTacToe::move_cursor_to_grid(&Coordinates { x: 1920, y: 1080 })?;
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
+    Ok(())
+}
+
+#[test]
+fn record_moves() -> Result<()> {
+    let mut tictactoe = TicTacToe::from_seed(Grid::from(Side(3)), AI::Random, 42)?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 1, y: 1, z: 0 })?;
+    tictactoe.mark_cross()?;
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 0, y: 2, z: 0 })?;
+    tictactoe.mark_zero()?;
+    let mut record = GameRecord::from(&tictactoe.board, 42);
+    record.moves = vec![
+        Move::place(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        Move::place(Coordinates { x: 0, y: 2, z: 0 }, Player::Zero),
+    ];
+    assert_eq!(tictactoe.record, record);
+    // Move the cursor as far in the bottom right corner as possible.
+    // Otherwise the terminal test output gets messed up.
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
+    Ok(())
+}
+
+#[test]
+fn move_list() -> Result<()> {
+    let mut tictactoe = TicTacToe::from_seed(Grid::from(Side(3)), AI::Random, 42)?;
+    assert_eq!(tictactoe.move_list(30), "");
+    tictactoe.record.moves = vec![
+        Move::place(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        Move::place(Coordinates { x: 0, y: 2, z: 0 }, Player::Zero),
+        Move::place(Coordinates { x: 2, y: 0, z: 0 }, Player::Cross),
+    ];
+    assert_eq!(tictactoe.move_list(30), "Moves: b2 a3 c1");
+    assert_eq!(tictactoe.move_list(14), "Moves: .. c1");
+    Ok(())
+}
+
+#[test]
+fn gravity_drops_marks() -> Result<()> {
+    let mut tictactoe = TicTacToe::from_seed(Grid::new(7, 6), AI::Random, 42)?;
+    let mut board = Board::new(7, 6, 4);
+    board.gravity = true;
+    tictactoe.set_board(board);
+    tictactoe.set_cursor_to_grid(&Coordinates { x: 2, y: 0, z: 0 })?;
+    tictactoe.mark_cross()?;
+    tictactoe.mark_zero()?;
+    assert_eq!(
+        tictactoe.board.get(&Coordinates { x: 2, y: 5, z: 0 }),
+        Some(&Player::Cross)
+    );
+    assert_eq!(
+        tictactoe.board.get(&Coordinates { x: 2, y: 4, z: 0 }),
+        Some(&Player::Zero)
+    );
+    assert!(tictactoe.record.gravity);
+    // Move the cursor as far in the bottom right corner as possible.
+    // Otherwise the terminal test output gets messed up.
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
+    Ok(())
+}
+
+#[test]
+fn undo_takes_back_the_ai_reply() -> Result<()> {
+    let mut tictactoe = tictactoe_from_settings()?;
+    assert!(tictactoe.undo().is_err());
+    for (position, player) in [
+        ("b2", Player::Cross),
+        ("a3", Player::Zero),
+        ("c1", Player::Cross),
+    ] {
+        tictactoe.set_cursor_to_grid(&position.parse()?)?;
+        match player {
+            Player::Cross => tictactoe.mark_cross()?,
+            _ => tictactoe.mark_zero()?,
+        };
+    }
+    tictactoe.undo()?;
+    assert_eq!(tictactoe.record.moves.len(), 2);
+    assert_eq!(tictactoe.board.get(&"c1".parse()?), None);
+    tictactoe.undo()?;
+    assert!(tictactoe.record.moves.is_empty());
+    assert_eq!(tictactoe.board, Board::from(Side(3)));
+    Ok(())
+}
+
+#[test]
+fn no_undo_after_a_swap_opening() -> Result<()> {
+    let mut tictactoe = tictactoe_from_settings()?;
+    tictactoe.set_swap(Swap::Pie, Player::Cross);
+    tictactoe.set_cursor_to_grid(&"b2".parse()?)?;
+    tictactoe.mark_cross()?;
+    assert!(tictactoe.undo().is_err());
+    Ok(())
+}
+
+#[test]
+fn hint_finds_the_win() -> Result<()> {
+    let mut tictactoe = tictactoe_from_settings()?;
+    let mut board = Board::from(Side(3));
+    for (position, player) in [
+        ("a1", Player::Cross),
+        ("b1", Player::Cross),
+        ("a2", Player::Zero),
+        ("b2", Player::Zero),
+    ] {
+        board.mark(position.parse()?, player)?;
+    }
+    tictactoe.set_position(board, Player::Cross);
+    let (evaluation, hint) = tictactoe.hint()?;
+    assert_eq!(hint.position, "c1".parse()?);
+    assert!(matches!(evaluation, Evaluation::Win(_)));
+    Ok(())
+}
+
+#[test]
+fn run_commands() -> Result<()> {
+    let mut tictactoe = tictactoe_from_settings()?;
+    assert_eq!(
+        tictactoe.run_command("b2".parse()?)?,
+        Some(InputEvent::Mark)
+    );
+    assert_eq!(tictactoe.cursor, Coordinates { x: 1, y: 1, z: 0 });
+    assert!(tictactoe.run_command("d4".parse()?).is_err());
+    assert_eq!(tictactoe.run_command("ai mcts".parse()?)?, None);
+    assert_eq!(tictactoe.ai_algo, AI::MonteCarlo);
+    assert_eq!(
+        tictactoe.run_command("new 4 4".parse()?)?,
+        Some(InputEvent::Quit)
+    );
+    assert_eq!(tictactoe.requested, Some(GameCommand::New(Some((4, 4, 4)))));
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
+    Ok(())
+}
+
+/// Plays the given moves of crosses and zeros in turn under rules which
+/// leave each player two marks, each lifting the player's oldest mark once
+/// they have two, and returns how the game stood after each of them. Nobody
+/// can complete a line with two marks.
+fn play_lifting_oldest(rules: Variant, moves: &[&str]) -> Result<Vec<Option<Outcome>>> {
+    let mut tictactoe = tictactoe_from_settings()?;
+    tictactoe.set_rules(rules);
+    let mut player = Player::Cross;
+    let mut outcomes = Vec::new();
+    for position in moves {
+        let position: Coordinates = position.parse()?;
+        let next = tictactoe
+            .rules
+            .legal_moves(&tictactoe.board, player)
+            .into_iter()
+            .find(|next| {
+                next.position == position
+                    && next
+                        .from
+                        .iter()
+                        .all(|from| tictactoe.board.marks_of(player).first() == Some(from))
+            })
+            .expect("the move is legal");
+        outcomes.push(tictactoe.play_move(next)?);
+        player = player.opponent();
+    }
+    TicTacToe::move_cursor_to_grid(&Coordinates {
+        x: 1920,
+        y: 1080,
+        z: 0,
+    })?;
+    Ok(outcomes)
+}
+
+/// The position after crosses a1 a2 and zeros c1 c2, with crosses to move,
+/// comes up again twice: first with crosses' marks in the other order, and
+/// then as it was.
+const SAME_MARKS_THRICE: [&str; 22] = [
+    "a1", "c1", "a2", "c2", "b2", "c3", "a1", "b3", "b1", "a3", "a2", "c1", "a1", "c2", "b2", "c3",
+    "b1", "b3", "a1", "c1", "a2", "c2",
+];
+
+#[test]
+fn marks_of_other_ages_make_another_position() -> Result<()> {
+    let outcomes = play_lifting_oldest(Variant::Vanishing { marks: 2 }, &SAME_MARKS_THRICE)?;
+    assert!(outcomes.iter().all(Option::is_none));
+    Ok(())
+}
+
+#[test]
+fn third_repetition_draws() -> Result<()> {
+    let mut moves = SAME_MARKS_THRICE.to_vec();
+    moves.extend(["b1", "c3", "a1", "c1", "a2", "c2"]);
+    let outcomes = play_lifting_oldest(Variant::Vanishing { marks: 2 }, &moves)?;
+    assert!(outcomes[..moves.len() - 1].iter().all(Option::is_none));
+    assert_eq!(outcomes.last(), Some(&Some(Outcome::Draw)));
+    Ok(())
+}
+
+#[test]
+fn sliding_pieces_have_no_age() -> Result<()> {
+    let rules = Variant::Sliding {
+        pieces: 2,
+        movement: Movement::Anywhere,
+    };
+    let outcomes = play_lifting_oldest(rules, &SAME_MARKS_THRICE)?;
+    assert!(outcomes[..SAME_MARKS_THRICE.len() - 1]
+        .iter()
+        .all(Option::is_none));
+    assert_eq!(outcomes.last(), Some(&Some(Outcome::Draw)));
     Ok(())
 }
@@ -1,8 +1,15 @@
-use tictactoe::{Coordinates, Grid};
+use tictactoe::{Coordinates, Grid, Side};
 
 #[test]
 fn grid_coords_to_screen_coords() {
-    let grid_coords = Coordinates { x: 2, y: 1 };
+    let grid_coords = Coordinates { x: 2, y: 1, z: 0 };
     let screen_coords = Grid::grid_coords_to_screen_coords(&grid_coords);
-    assert_eq!(screen_coords, Coordinates { x: 9, y: 1 });
+    assert_eq!(screen_coords, Coordinates { x: 9, y: 1, z: 0 });
+}
+
+#[test]
+fn layered_screen_coords() {
+    let grid = Grid::layered(Side(4), 4);
+    let screen_coords = grid.screen_coords(&Coordinates { x: 2, y: 1, z: 1 });
+    assert_eq!(screen_coords, Coordinates { x: 27, y: 1, z: 0 });
 }
@@ -1,5 +1,21 @@
 mod ai;
+mod analysis;
+mod board;
+mod cli;
+mod clock;
 mod coordinates;
+mod editor;
 mod game;
 mod grid;
 mod player;
+mod prompt;
+mod quantum;
+mod rating;
+mod record;
+mod replay;
+mod rules;
+mod series;
+mod solver;
+mod stats;
+mod swap;
+mod ultimate;
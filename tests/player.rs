@@ -9,3 +9,14 @@ fn cross_to_char() {
 fn zero_to_char() {
     assert_eq!(Player::Zero.to_char(), '0')
 }
+
+#[test]
+fn turn_order() {
+    assert_eq!(Player::Cross.next(2), Player::Zero);
+    assert_eq!(Player::Zero.next(2), Player::Cross);
+    assert_eq!(Player::Zero.next(3), Player::Plus);
+    assert_eq!(Player::Plus.next(3), Player::Cross);
+    assert_eq!(Player::Plus.next(4), Player::Star);
+    assert_eq!(Player::from_char('*'), Some(Player::Star));
+    assert_eq!(Player::Plus.to_char(), '+');
+}
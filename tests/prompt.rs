@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+use tictactoe::prompt::complete;
+use tictactoe::{Coordinates, GameCommand, AI};
+
+#[test]
+fn parse_commands() {
+    let parse = |line: &str| line.parse::<GameCommand>().unwrap();
+    assert_eq!(
+        parse("b2"),
+        GameCommand::Mark(Coordinates { x: 1, y: 1, z: 0 })
+    );
+    assert_eq!(parse(":undo"), GameCommand::Undo);
+    assert_eq!(parse("hint"), GameCommand::Hint);
+    assert_eq!(parse("new"), GameCommand::New(None));
+    assert_eq!(parse("new 4 4"), GameCommand::New(Some((4, 4, 4))));
+    assert_eq!(parse("new 7 6 4"), GameCommand::New(Some((7, 6, 4))));
+    assert_eq!(parse("ai mcts"), GameCommand::Ai(AI::MonteCarlo));
+    assert_eq!(
+        parse("save game.txt"),
+        GameCommand::Save(PathBuf::from("game.txt"))
+    );
+}
+
+#[test]
+fn invalid_commands() {
+    for line in [
+        "", "undo now", "new 4", "new 0 3", "ai", "ai deep", "save", "jump",
+    ] {
+        assert!(line.parse::<GameCommand>().is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn tab_completion() {
+    assert_eq!(complete("un"), "undo ");
+    assert_eq!(complete("a"), "ai ");
+    assert_eq!(complete("ai m"), "ai m");
+    assert_eq!(complete("ai mc"), "ai mcts ");
+    assert_eq!(complete("ai r"), "ai random ");
+    assert_eq!(complete(""), "");
+    assert_eq!(complete("x"), "x");
+    assert_eq!(complete("save ga"), "save ga");
+}
@@ -0,0 +1,118 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::io::Result;
+use std::time::{Duration, Instant};
+use tictactoe::mcts::Playable;
+use tictactoe::quantum::{QuantumGrid, QuantumMove};
+use tictactoe::{Coordinates, Outcome, Player, QuantumBoard, AI};
+
+fn square(x: i16, y: i16) -> Coordinates {
+    Coordinates { x, y, z: 0 }
+}
+
+#[test]
+fn spooky_marks_take_two_squares() -> Result<()> {
+    let mut board = QuantumBoard::default();
+    assert_eq!(board.legal_moves().len(), 36);
+    board.mark(QuantumMove::Spooky(square(0, 0), square(1, 1)))?;
+    assert_eq!(board.to_move, Player::Zero);
+    assert_eq!(board.spooky_marks_at(&square(1, 1)).len(), 1);
+    assert_eq!(board.spooky_marks_at(&square(0, 0))[0].turn, 1);
+    assert!(board
+        .mark(QuantumMove::Spooky(square(2, 2), square(2, 2)))
+        .is_err());
+    assert!(board.mark(QuantumMove::Collapse(square(0, 0))).is_err());
+    Ok(())
+}
+
+#[test]
+fn cycle_collapses_entangled_marks() -> Result<()> {
+    let mut board = QuantumBoard::default();
+    board.mark(QuantumMove::Spooky(square(0, 0), square(1, 0)))?;
+    board.mark(QuantumMove::Spooky(square(1, 0), square(2, 0)))?;
+    board.mark(QuantumMove::Spooky(square(1, 1), square(2, 2)))?;
+    assert!(board.pending.is_none());
+    board.mark(QuantumMove::Spooky(square(2, 0), square(0, 0)))?;
+    // Zero closed the cycle, so cross picks how it collapses.
+    assert!(board.pending.is_some());
+    assert_eq!(board.to_move, Player::Cross);
+    assert_eq!(
+        board.legal_moves(),
+        vec![
+            QuantumMove::Collapse(square(2, 0)),
+            QuantumMove::Collapse(square(0, 0))
+        ]
+    );
+    board.mark(QuantumMove::Collapse(square(0, 0)))?;
+    assert_eq!(board.classical.get(&square(0, 0)), Some(&Player::Zero));
+    assert_eq!(board.classical.get(&square(1, 0)), Some(&Player::Cross));
+    assert_eq!(board.classical.get(&square(2, 0)), Some(&Player::Zero));
+    assert_eq!(board.turns[&square(0, 0)], 4);
+    // The mark which is not entangled with the cycle stays spooky, and cross
+    // goes on to make their own move.
+    assert_eq!(board.spooky.len(), 1);
+    assert_eq!(board.to_move, Player::Cross);
+    assert_eq!(board.outcome(), None);
+    Ok(())
+}
+
+#[test]
+fn earliest_line_wins_simultaneous_lines() -> Result<()> {
+    let mut board = QuantumBoard::default();
+    let moves = [
+        (square(0, 0), square(0, 1)),
+        (square(0, 1), square(1, 0)),
+        (square(1, 0), square(1, 1)),
+        (square(1, 1), square(2, 0)),
+        (square(0, 2), square(1, 2)),
+        (square(2, 1), square(0, 0)),
+        (square(2, 0), square(2, 1)),
+    ];
+    for (a, b) in moves.iter() {
+        board.mark(QuantumMove::Spooky(*a, *b))?;
+    }
+    board.mark(QuantumMove::Collapse(square(2, 0)))?;
+    // Cross has a line completed on turn 7 and zero one completed on turn 6.
+    assert_eq!(board.outcome(), Some(Outcome::Win(Player::Zero)));
+    assert!(board.legal_moves().is_empty());
+    Ok(())
+}
+
+#[test]
+fn random_games_finish() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..50 {
+        let mut board = QuantumBoard::default();
+        while board.outcome().is_none() {
+            let next = *board.legal_moves().choose(&mut rng).unwrap();
+            board.play(next);
+            // Every mark is either spooky or in a square of its own.
+            let classical = board.classical.marked_positions().len();
+            let spooky = board.spooky.len() - board.pending.map_or(0, |_| 1);
+            assert!(classical + spooky < board.turn as usize);
+            assert!(board.turn <= 10);
+        }
+    }
+}
+
+#[test]
+fn ai_plays_legal_moves() -> Result<()> {
+    let mut board = QuantumBoard::default();
+    board.mark(QuantumMove::Spooky(square(0, 0), square(1, 1)))?;
+    let mut rng = StdRng::seed_from_u64(0);
+    for ai in [AI::Random, AI::MonteCarlo].iter() {
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let next = ai.get_quantum_move(&board, &mut rng, deadline);
+        assert!(board.is_legal(&next));
+    }
+    Ok(())
+}
+
+#[test]
+fn grid_coords_to_screen_coords() {
+    assert_eq!(
+        QuantumGrid::grid_coords_to_screen_coords(&square(1, 2)),
+        Coordinates { x: 17, y: 9, z: 0 }
+    );
+}
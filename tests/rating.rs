@@ -0,0 +1,84 @@
+use std::time::Duration;
+use tictactoe::rating::{ai_name, expected_score, STARTING_RATING};
+use tictactoe::stats::GameResult;
+use tictactoe::{Ratings, AI};
+
+const SECOND: Duration = Duration::from_secs(1);
+
+#[test]
+fn expected_scores() {
+    assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+    assert!((expected_score(1800.0, 1400.0) - 10.0 / 11.0).abs() < 1e-9);
+    assert!(expected_score(1000.0, 1400.0) < 0.1);
+}
+
+#[test]
+fn update() {
+    let mut ratings = Ratings::default();
+    assert_eq!(ratings.rating("ada").elo, STARTING_RATING);
+    assert_eq!(ratings.rating(&ai_name(AI::Random, SECOND)).elo, 1000.0);
+    ratings.update("ada", "bob", GameResult::Win);
+    assert_eq!(ratings.rating("ada").elo, STARTING_RATING + 16.0);
+    assert_eq!(ratings.rating("bob").elo, STARTING_RATING - 16.0);
+    assert_eq!(ratings.rating("bob").games, 1);
+    ratings.update("ada", "bob", GameResult::Draw);
+    assert!(ratings.rating("ada").elo < STARTING_RATING + 16.0);
+    assert_eq!(
+        ratings.rating("ada").elo + ratings.rating("bob").elo,
+        2.0 * STARTING_RATING
+    );
+}
+
+#[test]
+fn closest_ai() {
+    let mut ratings = Ratings::default();
+    let closest_ai = |ratings: &Ratings, ais: &[AI]| ratings.closest_ai("ada", ais, SECOND);
+    // A new player is as far from the random AI as from the Monte Carlo one,
+    // and gets the weaker of the two.
+    assert_eq!(closest_ai(&ratings, &AI::ALL), Some(AI::Random));
+    for _ in 0..20 {
+        ratings.update("ada", &ai_name(AI::MonteCarlo, SECOND), GameResult::Win);
+    }
+    // The player is now rated above the Monte Carlo AI, which has lost
+    // rating, and closer to MiniMax than to the random AI.
+    assert_eq!(closest_ai(&ratings, &AI::ALL), Some(AI::MonteCarlo));
+    assert_eq!(
+        closest_ai(&ratings, &[AI::Random, AI::MiniMax]),
+        Some(AI::MiniMax)
+    );
+    assert_eq!(closest_ai(&ratings, &[]), None);
+}
+
+#[test]
+fn round_trip_and_leaderboard() {
+    let ratings: Ratings = "rating ada 1216.5 1\nrating ai:random 983.5 1\nrating bob 1300.0 4\n"
+        .parse()
+        .unwrap();
+    assert_eq!(ratings.to_string().parse::<Ratings>().unwrap(), ratings);
+    let leaderboard = ratings.leaderboard();
+    assert!(leaderboard[0].starts_with("  1. bob"));
+    assert!(leaderboard[2].starts_with("  3. random (AI)"));
+    assert!(leaderboard[2].ends_with("in 1 game"));
+    assert!("rating ada high 1".parse::<Ratings>().is_err());
+    assert!("rating ada NaN 1".parse::<Ratings>().is_err());
+    assert!("rating ada inf 1".parse::<Ratings>().is_err());
+}
+
+#[test]
+fn ais_are_rated_by_think_time() {
+    let fast = ai_name(AI::MiniMax, Duration::from_millis(100));
+    let slow = ai_name(AI::MiniMax, Duration::from_secs(10));
+    assert_eq!(fast, "ai:minimax:100");
+    assert_eq!(
+        ai_name(AI::Random, Duration::from_millis(100)),
+        ai_name(AI::Random, SECOND)
+    );
+    let mut ratings = Ratings::default();
+    ratings.update("ada", &fast, GameResult::Win);
+    assert!(ratings.rating(&fast).elo < ratings.rating(&slow).elo);
+    assert_eq!(ratings.rating(&slow).elo, 1800.0);
+    let leaderboard = ratings.leaderboard();
+    assert!(leaderboard
+        .iter()
+        .any(|line| line.contains("minimax (AI, 100ms)")));
+}
@@ -0,0 +1,170 @@
+use tictactoe::{Board, Coordinates, GameRecord, Move, Player, Side, Swap};
+
+fn sample_record() -> GameRecord {
+    let mut record = GameRecord::from(&Board::from(Side(3)), 42);
+    record.moves = vec![
+        Move::place(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        Move::place(Coordinates { x: 0, y: 2, z: 0 }, Player::Zero),
+    ];
+    record
+}
+
+#[test]
+fn to_string() {
+    assert_eq!(
+        sample_record().to_string(),
+        "seed 42\nboard 3x3x3\nrules classic\nmove X b2\nmove 0 a3\n"
+    );
+}
+
+#[test]
+fn round_trip() {
+    let record = sample_record();
+    assert_eq!(record.to_string().parse::<GameRecord>().unwrap(), record);
+}
+
+#[test]
+fn rules_default_to_classic() {
+    let record: GameRecord = "seed 1\nboard 3x3x3\n".parse().unwrap();
+    assert_eq!(record.rules, "classic");
+    let record: GameRecord = "seed 1\nboard 3x3x3\nrules misere\n".parse().unwrap();
+    assert_eq!(record.rules, "misere");
+    assert!("seed 1\nboard 3x3x3\nrules chess\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
+
+#[test]
+fn invalid_records() {
+    assert!("board 3x3x3\n".parse::<GameRecord>().is_err());
+    assert!("seed 1\n".parse::<GameRecord>().is_err());
+    assert!("seed 1\nboard 3x3x3\nmove Y 1 1\n"
+        .parse::<GameRecord>()
+        .is_err());
+    assert!("seed 1\nboard 3x3x3\nmove Xfoo b2\n"
+        .parse::<GameRecord>()
+        .is_err());
+    assert!("seed 1\nboard 3x3x3\nfirst 0O\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
+
+#[test]
+fn layered_round_trip() {
+    let mut record = GameRecord::from(&Board::layered(4, 4, 4, 4), 7);
+    record.moves = vec![Move::place(Coordinates { x: 1, y: 2, z: 3 }, Player::Cross)];
+    let text = record.to_string();
+    assert_eq!(
+        text,
+        "seed 7\nboard 4x4x4\nlayers 4\nrules classic\nmove X b3/4\n"
+    );
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert_eq!(record.board().depth, 4);
+}
+
+#[test]
+fn gravity_round_trip() {
+    let mut board = Board::new(7, 6, 4);
+    board.gravity = true;
+    let record = GameRecord::from(&board, 3);
+    let text = record.to_string();
+    assert_eq!(text, "seed 3\nboard 7x6x4\ngravity on\nrules classic\n");
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert!(record.board().gravity);
+}
+
+#[test]
+fn torus_round_trip() {
+    let mut board = Board::from(Side(3));
+    board.torus = true;
+    let record = GameRecord::from(&board, 3);
+    let text = record.to_string();
+    assert_eq!(text, "seed 3\nboard 3x3x3\ntorus on\nrules classic\n");
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert!(record.board().torus);
+}
+
+#[test]
+fn blocked_round_trip() -> std::io::Result<()> {
+    let board = Board::from_map(".#.\n...\n...\n")?;
+    let record = GameRecord::from(&board, 3);
+    let text = record.to_string();
+    assert_eq!(text, "seed 3\nboard 3x3x3\nblocked b1\nrules classic\n");
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert_eq!(record.board(), board);
+    assert!("seed 3\nboard 3x3x3\nblocked 3 0\n"
+        .parse::<GameRecord>()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn slide_round_trip() {
+    let mut record = sample_record();
+    record.moves.push(Move {
+        position: Coordinates { x: 2, y: 1, z: 0 },
+        symbol: Player::Cross,
+        from: Some(Coordinates { x: 1, y: 1, z: 0 }),
+    });
+    let text = record.to_string();
+    assert!(text.ends_with("move X c2 from b2\n"));
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+}
+
+#[test]
+fn swap_round_trip() {
+    let mut record = sample_record();
+    record.swap = Some(Swap::Pie);
+    record.swapped = true;
+    let text = record.to_string();
+    assert!(text.contains("rules classic\nswap pie\nsides swapped\nmove"));
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert!("seed 1\nboard 3x3x3\nsides swapped\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
+
+#[test]
+fn variations_round_trip() {
+    let mut record = sample_record();
+    record.variations = vec![vec![
+        Move::place(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        Move::place(Coordinates { x: 2, y: 2, z: 0 }, Player::Zero),
+    ]];
+    let text = record.to_string();
+    assert!(text.ends_with("move 0 a3\nvariation X b2, 0 c3\n"));
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert!("seed 1\nboard 3x3x3\nvariation X 1 1,\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
+
+#[test]
+fn setup_round_trip() {
+    let mut board = Board::from(Side(3));
+    board
+        .mark(Coordinates { x: 2, y: 0, z: 0 }, Player::Cross)
+        .unwrap();
+    board
+        .mark(Coordinates { x: 0, y: 1, z: 0 }, Player::Zero)
+        .unwrap();
+    let record = GameRecord::from_position(&board, Player::Zero, 7);
+    let text = record.to_string();
+    assert!(text.contains("setup 0 a2\nsetup X c1\nfirst 0\n"));
+    assert_eq!(text.parse::<GameRecord>().unwrap(), record);
+    assert!("seed 1\nboard 3x3x3\nsetup X 1 1\nsetup 0 1 1\n"
+        .parse::<GameRecord>()
+        .is_err());
+}
+
+#[test]
+fn numeric_positions() {
+    let record: GameRecord = "seed 42\nboard 3x3x3\nmove X 1 1\nmove 0 0 2\n"
+        .parse()
+        .unwrap();
+    assert_eq!(record, sample_record());
+    let record: GameRecord = "seed 7\nboard 4x4x4\nlayers 4\nmove X 1 2 3\n"
+        .parse()
+        .unwrap();
+    assert_eq!(record.moves[0].position, Coordinates { x: 1, y: 2, z: 3 });
+}
@@ -0,0 +1,57 @@
+use tictactoe::replay::positions;
+use tictactoe::{Coordinates, GameRecord, Player, Replay};
+
+const GAME: &str = "seed 1\nboard 3x3x3\nrules classic\nmove X 1 1\nmove 0 0 0\nmove X 2 2\n";
+
+#[test]
+fn positions_after_every_move() {
+    let record: GameRecord = GAME.parse().unwrap();
+    let positions = positions(&record).unwrap();
+    assert_eq!(positions.len(), 4);
+    assert!(positions[0].marked_positions().is_empty());
+    assert_eq!(
+        positions[2].get(&Coordinates { x: 0, y: 0, z: 0 }),
+        Some(&Player::Zero)
+    );
+    assert_eq!(positions[3].marked_positions().len(), 3);
+}
+
+#[test]
+fn sides_swapped_after_the_opening() {
+    let record: GameRecord =
+        "seed 1\nboard 3x3x3\nrules classic\nswap pie\nsides swapped\nmove X 1 1\nmove X 0 0\n"
+            .parse()
+            .unwrap();
+    let positions = positions(&record).unwrap();
+    let center = Coordinates { x: 1, y: 1, z: 0 };
+    assert_eq!(positions[1].get(&center), Some(&Player::Zero));
+    assert_eq!(positions[2].get(&center), Some(&Player::Zero));
+}
+
+#[test]
+fn stepping_through() {
+    let mut replay = Replay::new(GAME.parse().unwrap()).unwrap();
+    assert_eq!(replay.status(), "Start of the game, 3 moves");
+    replay.back();
+    assert_eq!(replay.current(), 0);
+    replay.forward();
+    replay.forward();
+    assert_eq!(replay.status(), "Move 2 of 3: 0 at a1");
+    replay.go_to(10);
+    assert_eq!(replay.current(), 3);
+    assert_eq!(replay.board().marked_positions().len(), 3);
+}
+
+#[test]
+fn wild_moves_name_the_player() {
+    let record: GameRecord = "seed 1\nboard 3x3x3\nrules wild\nmove 0 b2\nmove 0 a1\nmove X c3\n"
+        .parse()
+        .unwrap();
+    let mut replay = Replay::new(record).unwrap();
+    replay.forward();
+    assert_eq!(replay.status(), "Move 1 of 3: X plays 0 at b2");
+    replay.forward();
+    assert_eq!(replay.status(), "Move 2 of 3: 0 at a1");
+    replay.forward();
+    assert_eq!(replay.status(), "Move 3 of 3: X at c3");
+}
@@ -0,0 +1,384 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tictactoe::board::Undo;
+use tictactoe::rules::{pick_move, Movement, Pick};
+use tictactoe::{Board, Coordinates, Move, Outcome, Player, Rules, Side, Variant, AI};
+
+/// Both players place crosses, and whoever completes a line loses.
+#[derive(Debug)]
+struct Notakto;
+
+impl fmt::Display for Notakto {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "notakto")
+    }
+}
+
+impl Rules for Notakto {
+    fn legal_moves(&self, board: &Board, _player: Player) -> Vec<Move> {
+        board
+            .legal_moves()
+            .into_iter()
+            .map(|position| Move::place(position, Player::Cross))
+            .collect()
+    }
+
+    fn outcome(&self, board: &Board, position: &Coordinates, player: Player) -> Option<Outcome> {
+        if board.completes_line(position, &Player::Cross) {
+            Some(Outcome::Win(player.opponent()))
+        } else {
+            None
+        }
+    }
+
+    fn evaluate(&self, _board: &Board, _player: Player) -> i32 {
+        0
+    }
+}
+
+/// Every mark is echoed on the position mirrored across the middle column,
+/// so players only mark the left half of the board.
+#[derive(Debug)]
+struct Mirror;
+
+impl Mirror {
+    fn mirrored(board: &Board, position: Coordinates) -> Coordinates {
+        Coordinates {
+            x: board.width as i16 - 1 - position.x,
+            ..position
+        }
+    }
+}
+
+impl fmt::Display for Mirror {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mirror")
+    }
+}
+
+impl Rules for Mirror {
+    fn legal_moves(&self, board: &Board, player: Player) -> Vec<Move> {
+        board
+            .legal_moves()
+            .into_iter()
+            .filter(|position| position.x < board.width as i16 / 2)
+            .map(|position| Move::place(position, player))
+            .collect()
+    }
+
+    fn apply(&self, board: &mut Board, next: &Move) -> std::io::Result<Undo> {
+        let undo = board.play(next)?;
+        board.mark(Self::mirrored(board, next.position), next.symbol)?;
+        Ok(undo)
+    }
+
+    fn undo(&self, board: &mut Board, undo: &Undo) {
+        board.undo(undo);
+        board.unmark(&Self::mirrored(board, undo.next.position));
+    }
+}
+
+fn board_with_line() -> std::io::Result<Board> {
+    let mut board = Board::from(Side(3));
+    for x in 0..3 {
+        board.mark(Coordinates { x, y: 0, z: 0 }, Player::Cross)?;
+    }
+    Ok(board)
+}
+
+#[test]
+fn classic_line_wins() -> std::io::Result<()> {
+    let board = board_with_line()?;
+    assert_eq!(
+        Variant::Classic.outcome(&board, &Coordinates { x: 2, y: 0, z: 0 }, Player::Cross),
+        Some(Outcome::Win(Player::Cross))
+    );
+    Ok(())
+}
+
+#[test]
+fn misere_line_loses() -> std::io::Result<()> {
+    let board = board_with_line()?;
+    assert_eq!(
+        Variant::Misere.outcome(&board, &Coordinates { x: 2, y: 0, z: 0 }, Player::Cross),
+        Some(Outcome::Win(Player::Zero))
+    );
+    Ok(())
+}
+
+#[test]
+fn game_goes_on() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Cross)?;
+    assert_eq!(
+        Variant::Misere.outcome(&board, &Coordinates { x: 1, y: 1, z: 0 }, Player::Cross),
+        None
+    );
+    Ok(())
+}
+
+#[test]
+fn full_board_draws() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    let rows = ["X0X", "X0X", "0X0"];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, marker) in row.chars().enumerate() {
+            let position = Coordinates {
+                x: x as i16,
+                y: y as i16,
+                z: 0,
+            };
+            board.mark(position, Player::from_char(marker).unwrap())?;
+        }
+    }
+    let last = Coordinates { x: 2, y: 2, z: 0 };
+    assert_eq!(
+        Variant::Classic.outcome(&board, &last, Player::Zero),
+        Some(Outcome::Draw)
+    );
+    Ok(())
+}
+
+#[test]
+fn wild_line_of_either_symbol_wins() -> std::io::Result<()> {
+    let board = board_with_line()?;
+    assert_eq!(
+        Variant::Wild.outcome(&board, &Coordinates { x: 2, y: 0, z: 0 }, Player::Zero),
+        Some(Outcome::Win(Player::Zero))
+    );
+    Ok(())
+}
+
+#[test]
+fn order_and_chaos() -> std::io::Result<()> {
+    let board = board_with_line()?;
+    let last = Coordinates { x: 2, y: 0, z: 0 };
+    assert_eq!(
+        Variant::OrderAndChaos.outcome(&board, &last, Player::Zero),
+        Some(Outcome::Win(Player::Cross))
+    );
+    let mut board = Board::from(Side(3));
+    let rows = ["X0X", "X0X", "0X0"];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, marker) in row.chars().enumerate() {
+            let position = Coordinates {
+                x: x as i16,
+                y: y as i16,
+                z: 0,
+            };
+            board.mark(position, Player::from_char(marker).unwrap())?;
+        }
+    }
+    let last = Coordinates { x: 2, y: 2, z: 0 };
+    assert_eq!(
+        Variant::OrderAndChaos.outcome(&board, &last, Player::Zero),
+        Some(Outcome::Win(Player::Zero))
+    );
+    Ok(())
+}
+
+#[test]
+fn legal_moves() {
+    let board = Board::from(Side(3));
+    assert_eq!(Variant::Classic.legal_moves(&board, Player::Zero).len(), 9);
+    assert!(Variant::Classic
+        .legal_moves(&board, Player::Zero)
+        .iter()
+        .all(|next| next.symbol == Player::Zero));
+    assert_eq!(Variant::Wild.legal_moves(&board, Player::Zero).len(), 18);
+    assert_eq!(Variant::OrderAndChaos.default_board(), (6, 6, 5));
+}
+
+#[test]
+fn sliding_moves() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    let adjacent = Variant::Sliding {
+        pieces: 2,
+        movement: Movement::Adjacent,
+    };
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    assert_eq!(adjacent.legal_moves(&board, Player::Cross).len(), 8);
+    board.mark(Coordinates { x: 2, y: 2, z: 0 }, Player::Cross)?;
+    let slides = adjacent.legal_moves(&board, Player::Cross);
+    assert_eq!(slides.len(), 6);
+    assert!(slides.iter().all(|next| next.from.is_some()));
+    let anywhere = Variant::Sliding {
+        pieces: 2,
+        movement: Movement::Anywhere,
+    };
+    assert_eq!(anywhere.legal_moves(&board, Player::Cross).len(), 14);
+    Ok(())
+}
+
+#[test]
+fn sliding_from_str() {
+    assert_eq!(
+        "sliding".parse::<Variant>().unwrap(),
+        Variant::Sliding {
+            pieces: 3,
+            movement: Movement::Adjacent,
+        }
+    );
+    let rules: Variant = "sliding:4:anywhere".parse().unwrap();
+    assert_eq!(rules.to_string(), "sliding:4:anywhere");
+    assert!("sliding:0".parse::<Variant>().is_err());
+    assert!("sliding:3:diagonal".parse::<Variant>().is_err());
+}
+
+#[test]
+fn vanishing_moves_take_the_oldest_mark() -> std::io::Result<()> {
+    let rules = Variant::Vanishing { marks: 2 };
+    let mut board = Board::from(Side(3));
+    let oldest = Coordinates { x: 1, y: 1, z: 0 };
+    board.mark(oldest, Player::Zero)?;
+    assert!(rules
+        .legal_moves(&board, Player::Zero)
+        .iter()
+        .all(|next| next.from.is_none()));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Zero)?;
+    let moves = rules.legal_moves(&board, Player::Zero);
+    assert_eq!(moves.len(), 7);
+    assert!(moves.iter().all(|next| next.from == Some(oldest)));
+    assert_eq!(rules.max_moves(&board), None);
+    assert_eq!("vanishing:2".parse::<Variant>().unwrap(), rules);
+    assert_eq!(rules.to_string(), "vanishing:2");
+    assert!("vanishing:0".parse::<Variant>().is_err());
+    Ok(())
+}
+
+#[test]
+fn from_str() {
+    assert_eq!("classic".parse::<Variant>().unwrap(), Variant::Classic);
+    assert_eq!("misere".parse::<Variant>().unwrap(), Variant::Misere);
+    assert_eq!("wild".parse::<Variant>().unwrap(), Variant::Wild);
+    assert_eq!(
+        "order-and-chaos".parse::<Variant>().unwrap(),
+        Variant::OrderAndChaos
+    );
+    assert!("chess".parse::<Variant>().is_err());
+}
+
+#[test]
+fn custom_rules() -> std::io::Result<()> {
+    let mut board = Board::from(Side(3));
+    board.mark(Coordinates { x: 0, y: 0, z: 0 }, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 0, z: 0 }, Player::Cross)?;
+    let losing = Coordinates { x: 2, y: 0, z: 0 };
+    let moves = Notakto.legal_moves(&board, Player::Zero);
+    assert_eq!(moves.len(), 7);
+    assert!(moves.iter().all(|next| next.symbol == Player::Cross));
+    let mut rng = StdRng::seed_from_u64(1);
+    for ai in [AI::MiniMax, AI::MonteCarlo].iter() {
+        let next = ai.get_move_within(
+            &board,
+            &Notakto,
+            Player::Zero,
+            &mut rng,
+            Instant::now() + Duration::from_millis(200),
+        );
+        assert_eq!(next.symbol, Player::Cross);
+        assert_ne!(next.position, losing);
+    }
+    board.mark(losing, Player::Cross)?;
+    assert_eq!(
+        Notakto.outcome(&board, &losing, Player::Zero),
+        Some(Outcome::Win(Player::Cross))
+    );
+    assert_eq!(Notakto.next_player(&board, Player::Zero), Player::Cross);
+    assert_eq!(Notakto.to_string(), "notakto");
+    Ok(())
+}
+
+#[test]
+fn multiplayer() -> std::io::Result<()> {
+    let rules: Variant = "multiplayer".parse().unwrap();
+    assert_eq!(rules, Variant::Multiplayer { players: 3 });
+    assert_eq!(rules.to_string(), "multiplayer:3");
+    assert_eq!(rules.default_board(), (6, 6, 4));
+    assert!("multiplayer:2".parse::<Variant>().is_err());
+    assert!("multiplayer:5".parse::<Variant>().is_err());
+    let mut board = Board::from(Side(3));
+    assert_eq!(rules.next_player(&board, Player::Zero), Player::Plus);
+    assert_eq!(rules.next_player(&board, Player::Plus), Player::Cross);
+    for x in 0..3 {
+        board.mark(Coordinates { x, y: 1, z: 0 }, Player::Plus)?;
+    }
+    let last = Coordinates { x: 2, y: 1, z: 0 };
+    assert_eq!(
+        rules.outcome(&board, &last, Player::Plus),
+        Some(Outcome::Win(Player::Plus))
+    );
+    Ok(())
+}
+
+#[test]
+fn search_takes_back_what_the_rules_apply() -> std::io::Result<()> {
+    let board = Board::new(4, 4, 4);
+    let next = AI::MiniMax.get_move_within(
+        &board,
+        &Mirror,
+        Player::Cross,
+        &mut StdRng::seed_from_u64(1),
+        Instant::now() + Duration::from_millis(200),
+    );
+    assert!(next.position.x < 2);
+    let mut played = board.clone();
+    let undo = Mirror.apply(&mut played, &next)?;
+    assert_eq!(played.marked_positions().len(), 2);
+    Mirror.undo(&mut played, &undo);
+    assert_eq!(played, board);
+    Ok(())
+}
+
+#[test]
+fn moves_are_picked_as_on_the_board() -> std::io::Result<()> {
+    let rules = Variant::Sliding {
+        pieces: 2,
+        movement: Movement::Anywhere,
+    };
+    let mut board = Board::from(Side(3));
+    let piece = Coordinates { x: 0, y: 0, z: 0 };
+    let empty = Coordinates { x: 2, y: 2, z: 0 };
+    board.mark(piece, Player::Cross)?;
+    board.mark(Coordinates { x: 1, y: 1, z: 0 }, Player::Zero)?;
+    board.mark(Coordinates { x: 2, y: 0, z: 0 }, Player::Cross)?;
+    let pick = |position, selected| {
+        pick_move(
+            &board,
+            &rules,
+            Player::Cross,
+            Player::Cross,
+            position,
+            selected,
+        )
+    };
+    assert!(pick(empty, None).is_err());
+    assert_eq!(pick(piece, None)?, Pick::PickUp(piece));
+    assert_eq!(pick(piece, Some(piece))?, Pick::PutBack(piece));
+    assert_eq!(
+        pick(empty, Some(piece))?,
+        Pick::Move(Move {
+            position: empty,
+            symbol: Player::Cross,
+            from: Some(piece),
+        })
+    );
+
+    let mut board = Board::new(3, 3, 3);
+    board.gravity = true;
+    let top = Coordinates { x: 1, y: 0, z: 0 };
+    assert_eq!(
+        pick_move(
+            &board,
+            &Variant::Classic,
+            Player::Zero,
+            Player::Zero,
+            top,
+            None
+        )?,
+        Pick::Move(Move::place(Coordinates { x: 1, y: 2, z: 0 }, Player::Zero))
+    );
+    Ok(())
+}
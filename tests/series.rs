@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+use tictactoe::series::game_path;
+use tictactoe::{Outcome, Player, Seat, Series};
+
+#[test]
+fn players_take_turns_moving_first() {
+    let mut series = Series::new(3, vec![Seat::Human, Seat::Ai]);
+    assert_eq!(series.seats_for_next_game(), vec![Seat::Human, Seat::Ai]);
+    series.record(Outcome::Win(Player::Cross));
+    assert_eq!(series.seats_for_next_game(), vec![Seat::Ai, Seat::Human]);
+    // The AI plays crosses in the second game.
+    series.record(Outcome::Win(Player::Cross));
+    assert_eq!((series.wins(0), series.losses(0)), (1, 1));
+    assert_eq!((series.wins(1), series.losses(1)), (1, 1));
+    assert!(!series.is_over());
+    series.record(Outcome::Win(Player::Zero));
+    assert!(series.is_over());
+    assert_eq!(series.winner(), Some(1));
+    assert_eq!(
+        series.scoreboard(),
+        "Best of 3, game 3: You W1 L2 D0  AI W2 L1 D0"
+    );
+    assert_eq!(
+        series.summary(),
+        "Best of 3 after 3 games: AI won\n  You: 1 won, 2 lost, 0 drawn\n  AI: 2 won, 1 lost, 0 drawn\n"
+    );
+}
+
+#[test]
+fn series_ends_once_decided() {
+    let mut series = Series::new(5, vec![Seat::Human, Seat::Ai, Seat::Ai]);
+    series.rotate_seats = false;
+    series.record(Outcome::Draw);
+    series.record(Outcome::Win(Player::Plus));
+    series.record(Outcome::Win(Player::Plus));
+    assert!(!series.is_over());
+    series.record(Outcome::Win(Player::Plus));
+    assert!(series.is_over());
+    assert_eq!(series.name(2), "AI 3");
+    assert_eq!(series.winner(), Some(2));
+    assert_eq!(series.draws(), 1);
+    assert_eq!(series.seats_for_next_game(), series.seats);
+}
+
+#[test]
+fn games_are_saved_to_numbered_files() {
+    let path = Path::new("games/game.txt");
+    assert_eq!(game_path(path, 1), PathBuf::from("games/game.txt"));
+    assert_eq!(game_path(path, 2), PathBuf::from("games/game-2.txt"));
+    assert_eq!(game_path(Path::new("game"), 3), PathBuf::from("game-3"));
+}
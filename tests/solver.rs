@@ -0,0 +1,50 @@
+use tictactoe::solver::{self, TerminalPositions};
+use tictactoe::{Coordinates, Outcome, Player};
+
+#[test]
+fn solve_classic() {
+    let solution = solver::solve(3, 3, 3);
+    assert_eq!(solution.value, Outcome::Draw);
+    assert_eq!(solution.positions, 765);
+    assert_eq!(
+        solution.terminal_positions,
+        TerminalPositions {
+            cross_wins: 91,
+            zero_wins: 44,
+            draws: 3,
+        }
+    );
+    // Every first move holds the draw.
+    assert_eq!(solution.best_moves.len(), 9);
+}
+
+#[test]
+fn solve_two_in_a_row() {
+    let solution = solver::solve(2, 2, 2);
+    assert_eq!(solution.value, Outcome::Win(Player::Cross));
+}
+
+#[test]
+fn solve_narrow_board() {
+    // A single row of three is always blocked by the second player.
+    let solution = solver::solve(3, 1, 3);
+    assert_eq!(solution.value, Outcome::Draw);
+    assert_eq!(
+        solution.best_moves,
+        vec![
+            Coordinates { x: 0, y: 0, z: 0 },
+            Coordinates { x: 1, y: 0, z: 0 },
+            Coordinates { x: 2, y: 0, z: 0 },
+        ]
+    );
+}
+
+#[test]
+fn report_uses_algebraic_notation() {
+    let report = solver::solve(3, 1, 3).to_string();
+    assert!(
+        report.ends_with("Optimal first moves: a1 b1 c1"),
+        "{}",
+        report
+    );
+}
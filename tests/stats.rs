@@ -0,0 +1,59 @@
+use tictactoe::stats::{GameResult, PlayedGame};
+use tictactoe::Stats;
+
+fn game(opponent: &str, board: &str, result: GameResult, moves: usize) -> PlayedGame {
+    PlayedGame {
+        opponent: opponent.to_string(),
+        board: board.to_string(),
+        result,
+        moves,
+    }
+}
+
+fn sample_stats() -> Stats {
+    Stats {
+        games: vec![
+            game("minimax", "3x3x3", GameResult::Draw, 9),
+            game("random", "3x3x3", GameResult::Win, 7),
+            game("random", "6x6x4", GameResult::Win, 13),
+            game("minimax", "3x3x3", GameResult::Loss, 8),
+            game("mcts", "4x4x4x4", GameResult::Win, 21),
+            game("random", "3x3x3", GameResult::Win, 5),
+        ],
+    }
+}
+
+#[test]
+fn round_trip() {
+    let stats = sample_stats();
+    let text = stats.to_string();
+    assert!(text.starts_with("game minimax 3x3x3 draw 9\ngame random 3x3x3 win 7\n"));
+    assert_eq!(text.parse::<Stats>().unwrap(), stats);
+    assert!("game random 3x3x3 won 7".parse::<Stats>().is_err());
+}
+
+#[test]
+fn tallies() {
+    let stats = sample_stats();
+    let total = stats.total();
+    assert_eq!((total.wins, total.losses, total.draws), (4, 1, 1));
+    let minimax = stats.by_opponent()["minimax"];
+    assert_eq!((minimax.wins, minimax.losses, minimax.draws), (0, 1, 1));
+    assert_eq!(stats.by_board()["3x3x3"], 4);
+    assert_eq!(stats.average_length(), Some(10.5));
+    assert_eq!(stats.current_streak(), Some((GameResult::Win, 2)));
+    assert_eq!(stats.longest_winning_streak(), 2);
+    assert_eq!(Stats::default().average_length(), None);
+}
+
+#[test]
+fn load_and_append() {
+    let dir = std::env::temp_dir().join(format!("tictactoe-stats-{}", std::process::id()));
+    let path = dir.join("ada.stats");
+    assert_eq!(Stats::load(&path).unwrap(), Stats::default());
+    for game in &sample_stats().games {
+        Stats::append(&path, game).unwrap();
+    }
+    assert_eq!(Stats::load(&path).unwrap(), sample_stats());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,18 @@
+use tictactoe::{Player, Swap};
+
+#[test]
+fn opening() {
+    assert_eq!(Swap::Pie.opening(Player::Zero), vec![Player::Zero]);
+    assert_eq!(
+        Swap::Swap2.opening(Player::Cross),
+        vec![Player::Cross, Player::Zero, Player::Cross]
+    );
+}
+
+#[test]
+fn from_str() {
+    assert_eq!("pie".parse::<Swap>().unwrap(), Swap::Pie);
+    assert_eq!("swap2".parse::<Swap>().unwrap(), Swap::Swap2);
+    assert_eq!(Swap::Swap2.to_string(), "swap2");
+    assert!("swap3".parse::<Swap>().is_err());
+}
@@ -0,0 +1,115 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::io::Result;
+use std::time::{Duration, Instant};
+use tictactoe::mcts::Playable;
+use tictactoe::{Coordinates, Outcome, Player, UltimateBoard, AI};
+
+#[test]
+fn board_index_and_local_position() {
+    let position = Coordinates { x: 7, y: 4, z: 0 };
+    assert_eq!(UltimateBoard::board_index(&position), 5);
+    assert_eq!(
+        UltimateBoard::local_position(&position),
+        Coordinates { x: 1, y: 1, z: 0 }
+    );
+}
+
+#[test]
+fn move_sends_opponent_to_board() -> Result<()> {
+    let mut board = UltimateBoard::default();
+    assert_eq!(board.legal_moves().len(), 81);
+    // The top-right position of the centre board sends the opponent to the
+    // top-right board.
+    board.mark(Coordinates { x: 5, y: 3, z: 0 })?;
+    assert_eq!(board.active, Some(2));
+    assert_eq!(board.to_move, Player::Zero);
+    assert_eq!(board.legal_moves().len(), 9);
+    assert!(board.mark(Coordinates { x: 0, y: 0, z: 0 }).is_err());
+    assert!(board.mark(Coordinates { x: 6, y: 0, z: 0 }).is_ok());
+    Ok(())
+}
+
+/// Cross takes the top row of the top-left board, with zero sending cross back
+/// to it each time.
+fn take_top_left_board(board: &mut UltimateBoard) -> Result<()> {
+    let moves = [
+        Coordinates { x: 0, y: 0, z: 0 },
+        Coordinates { x: 1, y: 1, z: 0 },
+        Coordinates { x: 5, y: 5, z: 0 },
+        Coordinates { x: 6, y: 6, z: 0 },
+        Coordinates { x: 1, y: 0, z: 0 },
+        Coordinates { x: 3, y: 0, z: 0 },
+        Coordinates { x: 2, y: 0, z: 0 },
+    ];
+    for position in moves.iter() {
+        board.mark(*position)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn winning_small_board() -> Result<()> {
+    let mut board = UltimateBoard::default();
+    take_top_left_board(&mut board)?;
+    assert_eq!(board.results[0], Some(Outcome::Win(Player::Cross)));
+    assert_eq!(
+        board.big_board.get(&Coordinates { x: 0, y: 0, z: 0 }),
+        Some(&Player::Cross)
+    );
+    // The top-right position sends zero to the top-right board.
+    assert_eq!(board.active, Some(2));
+    Ok(())
+}
+
+#[test]
+fn closed_board_frees_choice() -> Result<()> {
+    let mut board = UltimateBoard::default();
+    take_top_left_board(&mut board)?;
+    // Zero sends cross to the top-left board, which has been won already.
+    board.mark(Coordinates { x: 6, y: 0, z: 0 })?;
+    assert_eq!(board.active, None);
+    assert!(board
+        .legal_moves()
+        .iter()
+        .all(|position| UltimateBoard::board_index(position) != 0));
+    assert!(board.mark(Coordinates { x: 2, y: 2, z: 0 }).is_err());
+    Ok(())
+}
+
+#[test]
+fn random_games_finish() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..20 {
+        let mut board = UltimateBoard::default();
+        while board.outcome().is_none() {
+            let position = *board.legal_moves().choose(&mut rng).unwrap();
+            board.play(position);
+            for index in 0..9 {
+                let big_position = Coordinates {
+                    x: index as i16 % 3,
+                    y: index as i16 / 3,
+                    z: 0,
+                };
+                if let Some(Outcome::Win(player)) = board.results[index] {
+                    assert_eq!(board.big_board.get(&big_position), Some(&player));
+                }
+            }
+            assert!(board.active.is_none_or(|active| board.is_open(active)));
+        }
+    }
+}
+
+#[test]
+fn ai_plays_legal_moves() -> Result<()> {
+    let mut board = UltimateBoard::default();
+    board.mark(Coordinates { x: 4, y: 4, z: 0 })?;
+    let mut rng = StdRng::seed_from_u64(0);
+    for ai in [AI::Random, AI::MonteCarlo].iter() {
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let position = ai.get_ultimate_marker(&board, &mut rng, deadline);
+        assert!(board.is_legal(&position));
+    }
+    Ok(())
+}